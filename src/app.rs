@@ -1,10 +1,45 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::PathBuf,
+};
 
-use anyhow::Result;
+use iced::{
+    Align, Application, Column, Command, Container, Element, Image, Length, Point, Row, Size, Text,
+};
+use iced_native::{keyboard, mouse};
 
-use iced::{Application, Column, Command, Container, Element, Length, Row, Text};
+use crate::{
+    bookmarks::{self, Bookmark, BookmarkStore, Viewport},
+    bookmarks_panel::{BookmarksMessage, BookmarksPanel, BOOKMARKS_PANEL_WIDTH},
+    comic::{Comic, ComicError, ComicWarning, Page, RenderSize},
+    comic_info::ComicMetadata,
+    comic_settings::{ComicSettings, ComicSettingsStore},
+    file_browser::{FileBrowser, FileBrowserMessage},
+    hud::{self, GoToPageDialog, GoToPageMessage},
+    image_viewer::{self, FitMode},
+    keymap::{Keymap, KeymapError},
+    library::{self, Library, Scan},
+    library_view::{LibraryMessage, LibraryView},
+    page_cache::{self, DecodedPage, PageCache},
+    page_navigator::{self, PageNavigator, PageNavigatorMessage, NAVIGATOR_HEIGHT},
+    page_strip::{PageStrip, StripLayout},
+    spread,
+    stack::Stack,
+};
 
-use crate::{comic::{Comic, ComicError, Page}, image_viewer};
+/// How the reader starts, as asked for on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    /// A comic to open right away.
+    pub path: Option<PathBuf>,
+    /// The page to open it at, counted from zero.
+    pub page: Option<usize>,
+    pub spread_mode: bool,
+    pub right_to_left: bool,
+    pub continuous_mode: bool,
+    pub fullscreen: bool,
+}
 
 #[derive(Debug, Default)]
 pub struct App {
@@ -12,10 +47,90 @@ pub struct App {
     is_dropping: bool,
     is_opening: bool,
     current_comic: Option<Comic>,
-    current_page_index: i32,
+    current_page_index: usize,
+    /// Where to start reading the comic being opened, instead of the cover.
+    start_page: Option<usize>,
     current_page_view: Option<PageView>,
+    /// Shown over everything else while picking a comic to open.
+    file_browser: Option<FileBrowser>,
+    /// Shown over the pages while typing where to go.
+    go_to_page_dialog: Option<GoToPageDialog>,
+    /// Where the reader is, shown over the pages for a moment after it changes.
+    is_hud_shown: bool,
+    /// Bumped whenever the HUD is shown again, so only the latest timeout
+    /// hides it.
+    hud_generation: u64,
+    /// What the HUD said when it last came up, to tell when that changes.
+    shown_hud_text: Option<String>,
+    /// A warning about the comic just opened, kept on screen until the
+    /// reader moves on.
+    shown_warning: Option<ComicWarning>,
+    /// The index of the library folders, opened the first time it is shown.
+    library: Option<Library>,
+    /// The cover grid, shown over the comic while it is open.
+    library_view: Option<LibraryView>,
+    is_scanning_library: bool,
+    /// Thumbnails of the pages along the bottom of the window, while shown.
+    page_navigator: Option<PageNavigator>,
+    page_cache: PageCache,
+    /// Bumped whenever a different comic is opened, so pages still being
+    /// decoded for the previous one are ignored when they arrive.
+    comic_generation: u64,
+    is_loading_page: bool,
+    /// Show two pages side by side, the way the book is printed.
+    spread_mode: bool,
+    /// Read manga style, spreads are laid out and turned from right to left.
+    right_to_left: bool,
+    /// The direction asked for on the command line, used for comics that
+    /// don't say which way they read.
+    default_right_to_left: bool,
+    /// How pages are sized to the window, remembered for each comic.
+    fit_mode: FitMode,
+    comic_settings: ComicSettingsStore,
+    /// Pages known to be two-page spreads on their own, by file name.
+    wide_pages: HashSet<String>,
+    /// Every page stacked in one long strip, for webtoons.
+    continuous_mode: bool,
+    /// Show what the comic's metadata says about it next to the pages.
+    show_info: bool,
+    /// The bookmarks of the comic being read, next to the pages while shown.
+    bookmarks_panel: Option<BookmarksPanel>,
+    bookmarks: BookmarkStore,
+    /// Where a bookmark being jumped to was zoomed into, waiting for its page
+    /// to be decoded, along with the page's file name.
+    pending_viewport: Option<(String, Viewport)>,
+    /// How far down the strip the top of the window is, in pixels.
+    strip_offset: f32,
+    /// Pixel sizes of the pages decoded so far, by file name. They outlive
+    /// the decoded pages so the strip doesn't change shape as the cache evicts.
+    page_sizes: HashMap<String, (u32, u32)>,
+    /// The last thing that went wrong, shown instead of the page until the
+    /// reader moves on.
+    error: Option<ComicError>,
+    window_width: u32,
+    window_height: u32,
+    cursor_position: Point,
+    click_started_at: Option<Point>,
+    keymap: Keymap,
+    keymap_errors: Vec<KeymapError>,
+    fullscreen: bool,
+    should_exit: bool,
 }
 
+/// How far the cursor may travel between press and release for the
+/// gesture to still count as a click rather than a drag of the page.
+const CLICK_DRAG_TOLERANCE: f32 = 4.0;
+
+/// How much of the window a page turn scrolls in continuous mode, leaving a
+/// little of the previous view in sight to keep your place.
+const STRIP_SCROLL_FRACTION: f32 = 0.9;
+
+/// Width of the metadata panel, in pixels.
+const INFO_PANEL_WIDTH: u32 = 320;
+
+/// How many library covers are decoded at the same time.
+const COVER_LOADS_AT_ONCE: usize = 2;
+
 #[derive(Debug, Clone)]
 pub enum WindowMessage {
     FileDropped(PathBuf),
@@ -23,12 +138,23 @@ pub enum WindowMessage {
     FileHoveredLeft,
     GainedFocus,
     LostFocus,
+    Resized { width: u32, height: u32 },
+    CursorMoved(Point),
+    LeftButtonPressed,
+    LeftButtonReleased,
+    WheelScrolled,
 }
 
 #[derive(Debug, Clone)]
 pub enum ComicMessage {
     NextPage,
     PreviousPage,
+    FirstPage,
+    LastPage,
+    /// Turn towards the left edge of the window, which is backwards when
+    /// reading left to right and forwards when reading right to left.
+    PageLeft,
+    PageRight,
 }
 
 #[derive(Debug, Clone)]
@@ -36,28 +162,199 @@ pub enum Message {
     WindowMessage(WindowMessage),
     ComicMessage(ComicMessage),
     ComicOpened(Result<Comic, ComicError>),
+    PageDecoded {
+        generation: u64,
+        file_name: String,
+        result: Result<DecodedPage, ComicError>,
+    },
+    KeyPressed(keyboard::KeyCode, keyboard::Modifiers),
+    Action(Action),
+    /// The page strip was scrolled down by this many pixels.
+    StripScrolled(f32),
+    FileBrowser(FileBrowserMessage),
+    Library(LibraryMessage),
+    LibraryScanned(Scan),
+    CoverLoaded {
+        path: PathBuf,
+        /// `None` if the comic couldn't be opened to find its cover.
+        cover: Option<iced::image::Handle>,
+    },
+    PageNavigator(PageNavigatorMessage),
+    GoToPage(GoToPageMessage),
+    Bookmarks(BookmarksMessage),
+    HideHud {
+        generation: u64,
+    },
+    PageThumbnailLoaded {
+        generation: u64,
+        file_name: String,
+        /// `None` if the page couldn't be decoded.
+        thumbnail: Option<iced::image::Handle>,
+    },
+}
+
+/// Something the reader can trigger from a key chord in the keymap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NextPage,
+    PreviousPage,
+    FirstPage,
+    LastPage,
+    GoToPage,
+    ZoomIn,
+    ZoomOut,
+    PageLeft,
+    PageRight,
+    FitPage,
+    FitWidth,
+    FitHeight,
+    ActualSize,
+    Stretch,
+    CyclePageOrder,
+    ToggleSpreadMode,
+    ToggleRightToLeft,
+    ToggleContinuousMode,
+    ToggleInfo,
+    TogglePageNavigator,
+    ToggleBookmarks,
+    ToggleFullscreen,
+    OpenFile,
+    ToggleLibrary,
+    Quit,
+}
+
+impl Action {
+    const ALL: [Action; 25] = [
+        Action::NextPage,
+        Action::PreviousPage,
+        Action::FirstPage,
+        Action::LastPage,
+        Action::GoToPage,
+        Action::PageLeft,
+        Action::PageRight,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::FitPage,
+        Action::FitWidth,
+        Action::FitHeight,
+        Action::ActualSize,
+        Action::Stretch,
+        Action::CyclePageOrder,
+        Action::ToggleSpreadMode,
+        Action::ToggleRightToLeft,
+        Action::ToggleContinuousMode,
+        Action::ToggleInfo,
+        Action::TogglePageNavigator,
+        Action::ToggleBookmarks,
+        Action::ToggleFullscreen,
+        Action::OpenFile,
+        Action::ToggleLibrary,
+        Action::Quit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::NextPage => "next_page",
+            Action::PreviousPage => "previous_page",
+            Action::FirstPage => "first_page",
+            Action::LastPage => "last_page",
+            Action::GoToPage => "go_to_page",
+            Action::PageLeft => "page_left",
+            Action::PageRight => "page_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::FitPage => "fit_page",
+            Action::FitWidth => "fit_width",
+            Action::FitHeight => "fit_height",
+            Action::ActualSize => "actual_size",
+            Action::Stretch => "stretch",
+            Action::CyclePageOrder => "cycle_page_order",
+            Action::ToggleSpreadMode => "toggle_spread_mode",
+            Action::ToggleRightToLeft => "toggle_right_to_left",
+            Action::ToggleContinuousMode => "toggle_continuous_mode",
+            Action::ToggleInfo => "toggle_info",
+            Action::TogglePageNavigator => "toggle_page_navigator",
+            Action::ToggleBookmarks => "toggle_bookmarks",
+            Action::ToggleFullscreen => "toggle_fullscreen",
+            Action::OpenFile => "open_file",
+            Action::ToggleLibrary => "toggle_library",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL
+            .iter()
+            .find(|action| action.name() == name)
+            .copied()
+    }
 }
 
 impl Application for App {
     type Executor = iced::executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
-        (App::default(), Command::none())
+    fn new(flags: Self::Flags) -> (Self, Command<Self::Message>) {
+        let (window_width, window_height) = iced::window::Settings::default().size;
+
+        let (keymap, keymap_errors) = Keymap::load();
+
+        for error in &keymap_errors {
+            eprintln!("keymap: {}", error);
+        }
+
+        let mut app = App {
+            window_width,
+            window_height,
+            keymap,
+            keymap_errors,
+            comic_settings: ComicSettingsStore::load(),
+            bookmarks: BookmarkStore::load(),
+            spread_mode: flags.spread_mode,
+            right_to_left: flags.right_to_left,
+            default_right_to_left: flags.right_to_left,
+            continuous_mode: flags.continuous_mode,
+            fullscreen: flags.fullscreen,
+            ..App::default()
+        };
+
+        let command = match flags.path {
+            Some(path) => {
+                let command = app.open_comic(path);
+                app.start_page = flags.page;
+
+                command
+            }
+            None => Command::none(),
+        };
+
+        (app, command)
     }
 
     fn title(&self) -> String {
         match &self.current_comic {
-            Some(comic) => format!("comik - {}", comic.title),
+            Some(comic) => format!("comik - {}", comic.display_title()),
             None => "comik".to_string(),
         }
     }
 
+    fn mode(&self) -> iced::window::Mode {
+        if self.fullscreen {
+            iced::window::Mode::Fullscreen
+        } else {
+            iced::window::Mode::Windowed
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.should_exit
+    }
+
     fn update(
         &mut self,
         message: Self::Message,
-        _clipboard: &mut iced::Clipboard,
+        clipboard: &mut iced::Clipboard,
     ) -> Command<Message> {
         match message {
             Message::WindowMessage(window_message) => match window_message {
@@ -69,10 +366,8 @@ impl Application for App {
                 }
                 WindowMessage::FileDropped(path) => {
                     self.is_dropping = false;
-                    self.is_opening = true;
-                    self.current_comic = None;
 
-                    return Command::perform(Comic::from_archive_path(path), Message::ComicOpened);
+                    return self.open_comic(path);
                 }
                 WindowMessage::GainedFocus => {
                     self.focused = true;
@@ -80,53 +375,484 @@ impl Application for App {
                 WindowMessage::LostFocus => {
                     self.focused = false;
                 }
+                WindowMessage::Resized { width, height } => {
+                    self.window_width = width;
+                    self.window_height = height;
+
+                    if self.continuous_mode {
+                        // The strip is laid out to the window width, so every page
+                        // changes height. Stay on the page being read.
+                        return self.show_current_page();
+                    }
+
+                    return self.redraw_scalable_pages();
+                }
+                WindowMessage::WheelScrolled => {
+                    // The image viewer zooms on the wheel without telling anyone.
+                    return self.redraw_scalable_pages();
+                }
+                WindowMessage::CursorMoved(position) => {
+                    self.cursor_position = position;
+                }
+                WindowMessage::LeftButtonPressed => {
+                    self.click_started_at = Some(self.cursor_position);
+                }
+                WindowMessage::LeftButtonReleased => {
+                    if let Some(origin) = self.click_started_at.take() {
+                        let delta = self.cursor_position - origin;
+
+                        // Dragging pans the page inside the image viewer, only a
+                        // stationary click turns it.
+                        if delta.x.abs() <= CLICK_DRAG_TOLERANCE
+                            && delta.y.abs() <= CLICK_DRAG_TOLERANCE
+                            && self.cursor_position.x < self.page_area_width() as f32
+                            && self.cursor_position.y < self.page_area_height() as f32
+                            && !self.is_overlay_open()
+                        {
+                            let comic_message =
+                                if self.cursor_position.x < self.page_area_width() as f32 / 2.0 {
+                                    ComicMessage::PageLeft
+                                } else {
+                                    ComicMessage::PageRight
+                                };
+
+                            return self.update(Message::ComicMessage(comic_message), clipboard);
+                        }
+                    }
+                }
             },
-            Message::ComicMessage(comic_message) => match comic_message {
-                ComicMessage::NextPage => {
-                    if let Some(current_comic) = &self.current_comic {
-                        let page_count = current_comic.pages.len();
-                        if self.current_page_index + 1 != (page_count as i32) {
-                            self.current_page_index += 1;
-
-                            let new_page = current_comic
-                                .pages
-                                .get(self.current_page_index as usize)
-                                .unwrap()
-                                .clone();
-
-                            self.current_page_view = Some(PageView::open_page(new_page).unwrap());
+            Message::ComicMessage(comic_message) => {
+                self.shown_warning = None;
+
+                let comic_message = match (comic_message, self.right_to_left) {
+                    (ComicMessage::PageLeft, false) | (ComicMessage::PageRight, true) => {
+                        ComicMessage::PreviousPage
+                    }
+                    (ComicMessage::PageLeft, true) | (ComicMessage::PageRight, false) => {
+                        ComicMessage::NextPage
+                    }
+                    (comic_message, _) => comic_message,
+                };
+
+                if self.continuous_mode {
+                    let scroll_distance = self.page_area_height() as f32 * STRIP_SCROLL_FRACTION;
+
+                    let offset = match comic_message {
+                        ComicMessage::NextPage => self.strip_offset + scroll_distance,
+                        ComicMessage::PreviousPage => self.strip_offset - scroll_distance,
+                        ComicMessage::FirstPage => 0.0,
+                        // Scrolling stops at the bottom of the strip.
+                        ComicMessage::LastPage => f32::MAX,
+                        ComicMessage::PageLeft | ComicMessage::PageRight => unreachable!(),
+                    };
+
+                    return self.scroll_strip_to(offset);
+                }
+
+                // Every stop in the book, a single page or a spread of two.
+                let stops = self.page_groups();
+
+                if stops.is_empty() {
+                    return Command::none();
+                }
+
+                let current_stop = stops
+                    .iter()
+                    .position(|stop| stop.contains(&self.current_page_index))
+                    .unwrap_or(0);
+
+                let new_stop = match comic_message {
+                    ComicMessage::NextPage => current_stop + 1,
+                    ComicMessage::PreviousPage => match current_stop.checked_sub(1) {
+                        Some(stop) => stop,
+                        None => return Command::none(),
+                    },
+                    ComicMessage::FirstPage => 0,
+                    ComicMessage::LastPage => stops.len() - 1,
+                    ComicMessage::PageLeft | ComicMessage::PageRight => unreachable!(),
+                };
+
+                if let Some(stop) = stops.get(new_stop) {
+                    if new_stop != current_stop {
+                        self.current_page_index = stop.start;
+
+                        return self.show_current_page();
+                    }
+                }
+            }
+            Message::KeyPressed(key_code, modifiers) => {
+                // The keymap is for reading, the file browser and library only
+                // listen for a way out.
+                if self.is_overlay_open() {
+                    if key_code == keyboard::KeyCode::Escape {
+                        self.file_browser = None;
+                        self.library_view = None;
+                        self.go_to_page_dialog = None;
+                    }
+
+                    return Command::none();
+                }
+
+                // Keys typed into the bookmarks panel aren't meant for the keymap.
+                if let Some(bookmarks_panel) = &mut self.bookmarks_panel {
+                    if bookmarks_panel.is_typing() {
+                        if key_code == keyboard::KeyCode::Escape {
+                            bookmarks_panel.stop_typing();
                         }
+
+                        return Command::none();
                     }
                 }
-                ComicMessage::PreviousPage => {
-                    if let Some(current_comic) = &self.current_comic {
-                        if self.current_page_index >= 1 {
-                            self.current_page_index -= 1;
 
-                            let new_page = current_comic
-                                .pages
-                                .get(self.current_page_index as usize)
-                                .unwrap()
-                                .clone();
+                if let Some(action) = self.keymap.action_for(key_code, modifiers) {
+                    return self.update(Message::Action(action), clipboard);
+                }
+            }
+            Message::Action(action) => match action {
+                Action::NextPage => {
+                    return self.update(Message::ComicMessage(ComicMessage::NextPage), clipboard)
+                }
+                Action::PreviousPage => {
+                    return self
+                        .update(Message::ComicMessage(ComicMessage::PreviousPage), clipboard)
+                }
+                Action::FirstPage => {
+                    return self.update(Message::ComicMessage(ComicMessage::FirstPage), clipboard)
+                }
+                Action::LastPage => {
+                    return self.update(Message::ComicMessage(ComicMessage::LastPage), clipboard)
+                }
+                Action::GoToPage => {
+                    if self.current_comic.is_some() {
+                        self.go_to_page_dialog = Some(GoToPageDialog::new());
+                    }
+                }
+                Action::PageLeft => {
+                    return self.update(Message::ComicMessage(ComicMessage::PageLeft), clipboard)
+                }
+                Action::PageRight => {
+                    return self.update(Message::ComicMessage(ComicMessage::PageRight), clipboard)
+                }
+                Action::ZoomIn => {
+                    if let Some(page_view) = &mut self.current_page_view {
+                        page_view.for_each_viewer(image_viewer::ImageViewerState::zoom_in);
+                    }
 
-                            self.current_page_view = Some(PageView::open_page(new_page).unwrap());
+                    return self.redraw_scalable_pages();
+                }
+                Action::ZoomOut => {
+                    if let Some(page_view) = &mut self.current_page_view {
+                        page_view.for_each_viewer(image_viewer::ImageViewerState::zoom_out);
+                    }
+                }
+                Action::FitPage => return self.set_fit_mode(FitMode::Best),
+                Action::FitWidth => return self.set_fit_mode(FitMode::Width),
+                Action::FitHeight => return self.set_fit_mode(FitMode::Height),
+                Action::ActualSize => return self.set_fit_mode(FitMode::ActualSize),
+                Action::Stretch => return self.set_fit_mode(FitMode::Stretch),
+                Action::CyclePageOrder => {
+                    if let Some(current_comic) = &mut self.current_comic {
+                        let current_page =
+                            current_comic.pages.get(self.current_page_index).cloned();
+
+                        current_comic.set_page_order(current_comic.page_order.next());
+
+                        // Stay on the page being read, it just moves within the book.
+                        if let Some(index) = current_page
+                            .and_then(|page| current_comic.pages.iter().position(|p| *p == page))
+                        {
+                            self.current_page_index = index;
                         }
+
+                        // The neighbours are different pages now.
+                        return self.show_current_page();
                     }
                 }
+                Action::ToggleSpreadMode => {
+                    self.spread_mode = !self.spread_mode;
+
+                    return self.show_current_page();
+                }
+                Action::ToggleRightToLeft => {
+                    self.right_to_left = !self.right_to_left;
+
+                    return self.show_current_page();
+                }
+                Action::ToggleContinuousMode => {
+                    self.continuous_mode = !self.continuous_mode;
+
+                    return self.show_current_page();
+                }
+                Action::ToggleInfo => {
+                    self.show_info = !self.show_info;
+
+                    // The pages have less room beside the panel.
+                    return self.show_current_page();
+                }
+                Action::TogglePageNavigator => {
+                    self.page_navigator = match self.page_navigator {
+                        Some(_) => None,
+                        None => Some(PageNavigator::new()),
+                    };
+
+                    // The pages have less room above the strip.
+                    return self.show_current_page();
+                }
+                Action::ToggleBookmarks => {
+                    self.bookmarks_panel = match self.bookmarks_panel {
+                        Some(_) => None,
+                        None => Some(BookmarksPanel::new()),
+                    };
+
+                    // The pages have less room beside the panel.
+                    return self.show_current_page();
+                }
+                Action::ToggleFullscreen => {
+                    self.fullscreen = !self.fullscreen;
+                }
+                Action::OpenFile => {
+                    let directory = self
+                        .current_comic
+                        .as_ref()
+                        .and_then(|comic| comic.path.parent().map(PathBuf::from))
+                        .or_else(dirs::home_dir)
+                        .unwrap_or_else(|| PathBuf::from("/"));
+
+                    self.file_browser = Some(FileBrowser::new(directory));
+                }
+                Action::ToggleLibrary => {
+                    if self.library_view.take().is_none() {
+                        return self.open_library();
+                    }
+                }
+                Action::Quit => {
+                    self.should_exit = true;
+                }
+            },
+            Message::StripScrolled(delta) => {
+                return self.scroll_strip_to(self.strip_offset + delta);
+            }
+            Message::FileBrowser(file_browser_message) => match file_browser_message {
+                FileBrowserMessage::Navigate(directory) => {
+                    if let Some(file_browser) = &mut self.file_browser {
+                        file_browser.navigate(directory);
+                    }
+                }
+                FileBrowserMessage::Open(path) => {
+                    self.file_browser = None;
+
+                    return self.open_comic(path);
+                }
+                FileBrowserMessage::Cancel => {
+                    self.file_browser = None;
+                }
             },
+            Message::Library(library_message) => match library_message {
+                LibraryMessage::Open(path) => {
+                    self.library_view = None;
+
+                    return self.open_comic(path);
+                }
+                LibraryMessage::Rescan => {
+                    return self.scan_library();
+                }
+                LibraryMessage::Close => {
+                    self.library_view = None;
+                }
+            },
+            Message::LibraryScanned(scan) => {
+                self.is_scanning_library = false;
+
+                let entries = match &mut self.library {
+                    Some(library) => library
+                        .apply_scan(&scan)
+                        .and_then(|_| library.entries())
+                        .map_err(|error| error.to_string()),
+                    None => return Command::none(),
+                };
+
+                if let Some(library_view) = &mut self.library_view {
+                    library_view.set_scanning(false);
+
+                    match entries {
+                        Ok(entries) => library_view.set_entries(entries),
+                        Err(error) => {
+                            eprintln!("library: {}", error);
+                            library_view.set_error(Some(error));
+                        }
+                    }
+
+                    return self.load_covers();
+                }
+            }
+            Message::CoverLoaded { path, cover } => {
+                let is_wanted = self
+                    .library_view
+                    .as_mut()
+                    .map_or(false, |library_view| library_view.set_cover(path, cover));
+
+                // Each cover that arrives makes room for the next one.
+                if is_wanted {
+                    return self.load_next_cover();
+                }
+            }
+            Message::PageNavigator(navigator_message) => {
+                if let PageNavigatorMessage::GoTo(index) = navigator_message {
+                    return self.go_to_page(index);
+                }
+
+                let page_count = self
+                    .current_comic
+                    .as_ref()
+                    .map_or(0, |comic| comic.pages.len());
+
+                if let Some(page_navigator) = &mut self.page_navigator {
+                    page_navigator.show_more(&navigator_message, page_count, self.window_width);
+                }
+
+                return self.load_page_thumbnails();
+            }
+            Message::GoToPage(go_to_page_message) => match go_to_page_message {
+                GoToPageMessage::InputChanged(input) => {
+                    if let Some(dialog) = &mut self.go_to_page_dialog {
+                        dialog.set_input(input);
+                    }
+                }
+                GoToPageMessage::Submit => {
+                    let page_count = self
+                        .current_comic
+                        .as_ref()
+                        .map_or(0, |comic| comic.pages.len());
+
+                    let target = self
+                        .go_to_page_dialog
+                        .as_mut()
+                        .and_then(|dialog| dialog.target(page_count));
+
+                    // The dialog stays open to say what was wrong with the input.
+                    if let Some(index) = target {
+                        self.go_to_page_dialog = None;
+
+                        return self.go_to_page(index);
+                    }
+                }
+            },
+            Message::Bookmarks(bookmarks_message) => {
+                return self.update_bookmarks(bookmarks_message)
+            }
+            Message::HideHud { generation } => {
+                if generation == self.hud_generation {
+                    self.is_hud_shown = false;
+                }
+            }
+            Message::PageThumbnailLoaded {
+                generation,
+                file_name,
+                thumbnail,
+            } => {
+                if generation != self.comic_generation {
+                    return Command::none();
+                }
+
+                if let Some(page_navigator) = &mut self.page_navigator {
+                    page_navigator.set_thumbnail(file_name, thumbnail);
+                }
+
+                // Each thumbnail that arrives makes room for the next one.
+                return self.load_page_thumbnails();
+            }
             Message::ComicOpened(result) => {
                 self.is_opening = false;
 
-                let comic = result.unwrap();
+                match result {
+                    Ok(comic) => {
+                        if let Some(page) = self.start_page.take() {
+                            self.current_page_index = page.min(comic.pages.len() - 1);
+                        }
 
-                let new_page = comic
-                    .pages
-                    .get(self.current_page_index as usize)
-                    .unwrap()
-                    .clone();
+                        self.fit_mode = self
+                            .comic_settings
+                            .get(&comic.path)
+                            .fit_mode
+                            .unwrap_or_default();
+
+                        self.right_to_left =
+                            comic.right_to_left.unwrap_or(self.default_right_to_left);
+
+                        self.shown_warning = comic.warning;
+
+                        self.current_comic = Some(comic);
 
-                self.current_page_view = Some(PageView::open_page(new_page).unwrap());
-                self.current_comic = Some(comic);
+                        return self.show_current_page();
+                    }
+                    Err(error) => {
+                        self.start_page = None;
+                        eprintln!("could not open comic: {}", error);
+                        self.error = Some(error);
+                    }
+                }
+            }
+            Message::PageDecoded {
+                generation,
+                file_name,
+                result,
+            } => {
+                if generation != self.comic_generation {
+                    return Command::none();
+                }
+
+                let is_visible = self.visible_pages().contains(&file_name.as_str());
+
+                match result {
+                    Ok(decoded_page) => {
+                        let current_page_top = self.current_strip_page_top();
+
+                        self.page_sizes
+                            .insert(file_name.clone(), (decoded_page.width, decoded_page.height));
+
+                        let became_wide = spread::is_wide(decoded_page.width, decoded_page.height)
+                            && self.wide_pages.insert(file_name.clone());
+
+                        let handle = decoded_page.handle.clone();
+                        self.page_cache.insert(file_name.clone(), decoded_page);
+
+                        // A scalable page drawn again for a new size swaps in
+                        // without losing the zoom.
+                        if let Some(page_view) = &mut self.current_page_view {
+                            page_view.replace_image(&file_name, handle);
+                        }
+
+                        if self.continuous_mode {
+                            // Pages above the one being read change height as their real
+                            // size becomes known, move along so the view doesn't jump.
+                            if let (Some(before), Some(after)) =
+                                (current_page_top, self.current_strip_page_top())
+                            {
+                                self.strip_offset += after - before;
+                            }
+
+                            // Pages that turned out shorter can bring more into view.
+                            return self.prefetch_pages();
+                        }
+
+                        // A page that turns out to be a spread by itself changes
+                        // how the pages around it are paired up.
+                        if (is_visible && self.current_page_view.is_none())
+                            || (became_wide && self.spread_mode)
+                        {
+                            return self.show_current_page();
+                        }
+                    }
+                    Err(error) => {
+                        self.page_cache.cancel_pending(&file_name);
+
+                        if is_visible {
+                            eprintln!("could not open page: {}", error);
+                            self.is_loading_page = false;
+                            self.error = Some(error);
+                        }
+                    }
+                }
             }
         };
 
@@ -151,6 +877,40 @@ impl Application for App {
                 iced_native::window::Event::FilesHoveredLeft => {
                     Some(Message::WindowMessage(WindowMessage::FileHoveredLeft))
                 }
+                iced_native::window::Event::Resized { width, height } => {
+                    Some(Message::WindowMessage(WindowMessage::Resized {
+                        width,
+                        height,
+                    }))
+                }
+                _ => None,
+            },
+            iced_native::Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) => Some(Message::KeyPressed(key_code, modifiers)),
+            iced_native::Event::Mouse(mouse_event) => match mouse_event {
+                mouse::Event::CursorMoved { position } => {
+                    Some(Message::WindowMessage(WindowMessage::CursorMoved(position)))
+                }
+                // The image viewer captures left clicks to start a drag, so these are
+                // forwarded regardless of the event status.
+                mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                    Some(Message::WindowMessage(WindowMessage::LeftButtonPressed))
+                }
+                mouse::Event::ButtonReleased(mouse::Button::Left) => {
+                    Some(Message::WindowMessage(WindowMessage::LeftButtonReleased))
+                }
+                mouse::Event::WheelScrolled { .. } => {
+                    Some(Message::WindowMessage(WindowMessage::WheelScrolled))
+                }
+                // Side buttons, usually labelled back/forward.
+                mouse::Event::ButtonPressed(mouse::Button::Other(8)) => {
+                    Some(Message::ComicMessage(ComicMessage::PreviousPage))
+                }
+                mouse::Event::ButtonPressed(mouse::Button::Other(9)) => {
+                    Some(Message::ComicMessage(ComicMessage::NextPage))
+                }
                 _ => None,
             },
             _ => None,
@@ -158,56 +918,1131 @@ impl Application for App {
     }
 
     fn view(&mut self) -> Element<'_, Self::Message> {
-        let content = match &mut self.current_page_view {
-            Some(page_view) => Row::new()
+        // Borrowing the browser in an `if let` would keep it borrowed for the
+        // rest of the function, as the view it returns lives as long as `self`.
+        if self.file_browser.is_some() {
+            return self
+                .file_browser
+                .as_mut()
+                .expect("file browser is open")
+                .view()
+                .map(Message::FileBrowser);
+        }
+
+        if self.library_view.is_some() {
+            let width = self.window_width;
+
+            return self
+                .library_view
+                .as_mut()
+                .expect("library is open")
+                .view(width)
+                .map(Message::Library);
+        }
+
+        // Built before the pages, which keep `self` borrowed.
+        let info_panel = match &self.current_comic {
+            Some(comic) if self.show_info => Some(info_panel(comic)),
+            _ => None,
+        };
+
+        let current_pages = self.current_page_group();
+        let page_count = self
+            .current_comic
+            .as_ref()
+            .map_or(0, |comic| comic.pages.len());
+        let hud_text = self.hud_text().filter(|_| self.is_hud_shown);
+        let warning = self.shown_warning.as_ref().map(ToString::to_string);
+        let window_width = self.window_width;
+        let right_to_left = self.right_to_left;
+
+        // The pages, the strip and the navigator each borrow only their own
+        // part of `self`, so they can all be on screen at once.
+        let page_area = if self.continuous_mode && self.current_comic.is_some() {
+            self.page_strip()
+        } else if self.current_page_view.is_some() {
+            page_area(
+                Row::new().width(Length::Fill).height(Length::Fill).push(
+                    self.current_page_view
+                        .as_mut()
+                        .expect("page view is shown")
+                        .view(),
+                ),
+            )
+        } else {
+            page_area(self.status_message())
+        };
+
+        let page_area = match (&mut self.go_to_page_dialog, hud_text) {
+            (Some(dialog), _) => Stack::new(page_area)
+                .push(dialog.view(page_count).map(Message::GoToPage))
+                .into(),
+            (None, Some(hud_text)) => Stack::new(page_area).push(hud::status(hud_text)).into(),
+            _ => page_area,
+        };
+
+        let page_area = match warning {
+            Some(warning) => Stack::new(page_area).push(hud::warning(warning)).into(),
+            None => page_area,
+        };
+
+        let bookmarks_panel = match (&mut self.bookmarks_panel, &self.current_comic) {
+            (Some(bookmarks_panel), Some(comic)) => Some(
+                bookmarks_panel
+                    .view(
+                        self.bookmarks.get(&comic.path),
+                        &bookmarks::export_path(&comic.path).to_string_lossy(),
+                    )
+                    .map(Message::Bookmarks),
+            ),
+            _ => None,
+        };
+
+        let page_area = match (info_panel, bookmarks_panel) {
+            (None, None) => page_area,
+            (info_panel, bookmarks_panel) => info_panel
+                .into_iter()
+                .chain(bookmarks_panel)
+                .fold(
+                    Row::new()
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .push(page_area),
+                    Row::push,
+                )
+                .into(),
+        };
+
+        match (&mut self.page_navigator, &self.current_comic) {
+            (Some(page_navigator), Some(comic)) => Column::new()
                 .width(Length::Fill)
                 .height(Length::Fill)
-                .push(page_view.view()),
-            None => match self.is_opening {
-                true => Row::new()
-                    .width(Length::Shrink)
-                    .push(Text::new("Loading Comic File")),
-                false => Row::new()
-                    .width(Length::Shrink)
-                    .push(Text::new("No Comic Loaded")),
+                .push(page_area)
+                .push(
+                    page_navigator
+                        .view(&comic.pages, current_pages, window_width, right_to_left)
+                        .map(Message::PageNavigator),
+                )
+                .into(),
+            _ => page_area,
+        }
+    }
+}
+
+fn decode_page(page: &Page, render_size: RenderSize, generation: u64) -> Command<Message> {
+    let file_name = page.file_name().to_string();
+
+    Command::perform(
+        page_cache::decode_page(page.clone(), render_size),
+        move |result| Message::PageDecoded {
+            generation,
+            file_name: file_name.clone(),
+            result,
+        },
+    )
+}
+
+/// The pages of the current spread, or whatever is shown in their place,
+/// centred in the room left for them.
+fn page_area<'a>(content: Row<'a, Message>) -> Element<'a, Message> {
+    Container::new(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+}
+
+/// What is known about the comic, laid out for the side panel.
+fn info_panel<'a>(comic: &Comic) -> Element<'a, Message> {
+    let header = Column::new()
+        .spacing(4)
+        .push(Text::new(comic.display_title()).size(24))
+        .push(Text::new(format!("{} pages", comic.pages.len())).size(16));
+
+    let header = match &comic.warning {
+        Some(warning) => header.push(Text::new(format!("Warning: {}", warning)).size(16)),
+        None => header,
+    };
+
+    let fields = comic
+        .metadata
+        .as_ref()
+        .map(ComicMetadata::fields)
+        .unwrap_or_default();
+
+    let panel = if fields.is_empty() {
+        Column::new().push(Text::new("This comic has no metadata").size(16))
+    } else {
+        fields
+            .into_iter()
+            .fold(Column::new().spacing(12), |column, (label, value)| {
+                column.push(
+                    Column::new()
+                        .spacing(2)
+                        .push(Text::new(label).size(14))
+                        .push(Text::new(value).size(18)),
+                )
+            })
+    };
+
+    Column::new()
+        .width(Length::Units(INFO_PANEL_WIDTH as u16))
+        .height(Length::Fill)
+        .padding(20)
+        .spacing(20)
+        .push(header)
+        .push(panel)
+        .into()
+}
+
+impl App {
+    /// Forgets the comic being read and starts opening the one at `path`.
+    fn open_comic(&mut self, path: PathBuf) -> Command<Message> {
+        self.is_opening = true;
+        self.error = None;
+        self.shown_warning = None;
+        self.current_comic = None;
+        self.current_page_view = None;
+        self.current_page_index = 0;
+        self.start_page = None;
+        self.go_to_page_dialog = None;
+        self.pending_viewport = None;
+        self.page_cache.clear();
+        self.comic_generation += 1;
+        self.is_loading_page = false;
+        self.wide_pages.clear();
+        self.page_sizes.clear();
+        self.strip_offset = 0.0;
+
+        if self.page_navigator.is_some() {
+            self.page_navigator = Some(PageNavigator::new());
+        }
+
+        Command::perform(Comic::from_archive_path(path), Message::ComicOpened)
+    }
+
+    /// The pages shown together at each step through the book, which is
+    /// every page on its own unless spread mode is on.
+    fn page_groups(&self) -> Vec<Range<usize>> {
+        let pages = match &self.current_comic {
+            Some(comic) => &comic.pages,
+            None => return Vec::new(),
+        };
+
+        if self.spread_mode {
+            spread::layout(pages.len(), |index| {
+                pages[index].is_double_page() || self.wide_pages.contains(pages[index].file_name())
+            })
+        } else {
+            (0..pages.len()).map(|index| index..index + 1).collect()
+        }
+    }
+
+    /// The page or spread being read.
+    fn current_page_group(&self) -> Range<usize> {
+        let current = self.current_page_index;
+
+        self.page_groups()
+            .into_iter()
+            .find(|group| group.contains(&current))
+            .unwrap_or(current..current + 1)
+    }
+
+    /// File names of the pages on screen, in reading order.
+    fn visible_pages(&self) -> Vec<&str> {
+        let current = self.current_page_index;
+
+        let group = match self
+            .page_groups()
+            .into_iter()
+            .find(|group| group.contains(&current))
+        {
+            Some(group) => group,
+            None => return Vec::new(),
+        };
+
+        self.current_comic
+            .as_ref()
+            .map(|comic| {
+                comic.pages[group]
+                    .iter()
+                    .map(|page| page.file_name())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Jumps to the page at `index`, or the last page if the comic is shorter.
+    fn go_to_page(&mut self, index: usize) -> Command<Message> {
+        let page_count = match &self.current_comic {
+            Some(comic) => comic.pages.len(),
+            None => return Command::none(),
+        };
+
+        self.current_page_index = index.min(page_count.saturating_sub(1));
+
+        self.show_current_page()
+    }
+
+    fn update_bookmarks(&mut self, message: BookmarksMessage) -> Command<Message> {
+        let comic_path = match &self.current_comic {
+            Some(comic) => comic.path.clone(),
+            None => return Command::none(),
+        };
+
+        let export_path = self
+            .bookmarks_panel
+            .as_ref()
+            .and_then(BookmarksPanel::path)
+            .unwrap_or_else(|| bookmarks::export_path(&comic_path));
+
+        let bookmarks_panel = match &mut self.bookmarks_panel {
+            Some(bookmarks_panel) => bookmarks_panel,
+            None => return Command::none(),
+        };
+
+        match message {
+            BookmarksMessage::LabelChanged(label) => bookmarks_panel.set_label(label),
+            BookmarksMessage::NoteChanged(note) => bookmarks_panel.set_note(note),
+            BookmarksMessage::PathChanged(path) => bookmarks_panel.set_path(path),
+            BookmarksMessage::Add => {
+                let (label, note) = bookmarks_panel.take_input();
+
+                if let Some(bookmark) = self.current_bookmark(label, note) {
+                    self.bookmarks.add(&comic_path, bookmark);
+                }
+            }
+            BookmarksMessage::GoTo(index) => {
+                if let Some(bookmark) = self.bookmarks.get(&comic_path).get(index).cloned() {
+                    return self.go_to_bookmark(bookmark);
+                }
+            }
+            BookmarksMessage::Delete(index) => self.bookmarks.remove(&comic_path, index),
+            BookmarksMessage::Export => {
+                let bookmarks = self.bookmarks.get(&comic_path);
+
+                let status = match bookmarks::export(&export_path, &comic_path, bookmarks) {
+                    Ok(()) => format!(
+                        "Exported {} bookmarks to {:?}",
+                        bookmarks.len(),
+                        export_path
+                    ),
+                    Err(error) => format!("Could not export to {:?}: {}", export_path, error),
+                };
+
+                bookmarks_panel.set_status(Some(status));
+            }
+            BookmarksMessage::Import => {
+                let status = match bookmarks::import(&export_path, &comic_path) {
+                    Ok(imported) => {
+                        let count = imported.bookmarks.len();
+                        let added = self.bookmarks.merge(&comic_path, imported.bookmarks);
+
+                        let status = format!(
+                            "Imported {} of {} bookmarks, the rest were here already",
+                            added, count
+                        );
+
+                        match imported.other_comic {
+                            Some(other_comic) => {
+                                format!(
+                                    "{}. They were exported from {:?}, check they fit",
+                                    status, other_comic
+                                )
+                            }
+                            None => status,
+                        }
+                    }
+                    Err(error) => format!("Could not import {:?}: {}", export_path, error),
+                };
+
+                bookmarks_panel.set_status(Some(status));
+            }
+            BookmarksMessage::Close => {
+                self.bookmarks_panel = None;
+
+                return self.show_current_page();
+            }
+        }
+
+        Command::none()
+    }
+
+    /// A bookmark for the page being read, with how it is zoomed in unless
+    /// the pages are in one long strip.
+    fn current_bookmark(&self, label: String, note: String) -> Option<Bookmark> {
+        let comic = self.current_comic.as_ref()?;
+        let page = comic.pages.get(self.current_page_index)?;
+
+        let viewport = match &self.current_page_view {
+            Some(page_view) if !self.continuous_mode => page_view
+                .images
+                .iter()
+                .find(|image| image.file_name == page.file_name())
+                .and_then(|image| {
+                    let (image_size, bounds) = self.viewer_geometry(&image.file_name)?;
+
+                    Some(Viewport {
+                        zoom: image.image_viewer.scale(),
+                        region: image.image_viewer.viewport(image_size, bounds),
+                    })
+                }),
+            _ => None,
+        };
+
+        Some(Bookmark {
+            page: self.current_page_index,
+            file_name: Some(page.file_name().to_string()),
+            viewport,
+            label,
+            note,
+        })
+    }
+
+    /// Jumps to the page of a bookmark, looked up by its file name in case
+    /// the pages are in a different order, and zooms in the way it was.
+    fn go_to_bookmark(&mut self, bookmark: Bookmark) -> Command<Message> {
+        let page = match &self.current_comic {
+            Some(comic) => bookmark
+                .file_name
+                .as_deref()
+                .and_then(|file_name| {
+                    comic
+                        .pages
+                        .iter()
+                        .position(|page| page.file_name() == file_name)
+                })
+                .unwrap_or(bookmark.page),
+            None => return Command::none(),
+        };
+
+        self.pending_viewport = match (bookmark.file_name, bookmark.viewport) {
+            (Some(file_name), Some(viewport)) if !self.continuous_mode => {
+                Some((file_name, viewport))
+            }
+            _ => None,
+        };
+
+        self.go_to_page(page)
+    }
+
+    /// Zooms into the page of the bookmark just jumped to, once it is shown.
+    fn restore_bookmark_viewport(&mut self) {
+        let (file_name, viewport) = match &self.pending_viewport {
+            Some(pending) if self.current_page_view.is_some() => pending.clone(),
+            _ => return,
+        };
+
+        self.pending_viewport = None;
+
+        let (image_size, bounds) = match self.viewer_geometry(&file_name) {
+            Some(geometry) => geometry,
+            None => return,
+        };
+
+        let image = self.current_page_view.as_mut().and_then(|page_view| {
+            page_view
+                .images
+                .iter_mut()
+                .find(|image| image.file_name == file_name)
+        });
+
+        if let Some(image) = image {
+            image
+                .image_viewer
+                .show_viewport(viewport.zoom, viewport.region, image_size, bounds);
+        }
+    }
+
+    /// The pixel size of a page on screen and the size of the viewer it is in.
+    fn viewer_geometry(&self, file_name: &str) -> Option<(Size, Size)> {
+        let (width, height) = self.page_sizes.get(file_name)?;
+
+        let pages_across = self
+            .current_page_view
+            .as_ref()
+            .map_or(1, |page_view| page_view.images.len().max(1));
+
+        let bounds = Size::new(
+            (self.page_area_width() / pages_across as u32) as f32,
+            self.page_area_height() as f32,
+        );
+
+        Some((Size::new(*width as f32, *height as f32), bounds))
+    }
+
+    /// Shows the pages at `current_page_index` if they have been decoded
+    /// already, and starts decoding them and their neighbours otherwise.
+    fn show_current_page(&mut self) -> Command<Message> {
+        let current = self.current_page_index;
+
+        // Toggling spread mode or learning about a wide page can leave the
+        // index in the middle of a spread.
+        if let Some(group) = self
+            .page_groups()
+            .into_iter()
+            .find(|group| group.contains(&current))
+        {
+            self.current_page_index = group.start;
+        }
+
+        if self.continuous_mode {
+            return match self.current_strip_page_top() {
+                Some(top) => self.scroll_strip_to(top),
+                None => Command::none(),
+            };
+        }
+
+        let mut file_names: Vec<String> = self
+            .visible_pages()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+
+        if file_names.is_empty() {
+            return Command::none();
+        }
+
+        if self.right_to_left {
+            file_names.reverse();
+        }
+
+        let is_already_shown = self
+            .current_page_view
+            .as_ref()
+            .map_or(false, |page_view| page_view.file_names() == file_names);
+
+        if is_already_shown {
+            // Keep the zoom and position the reader has set up.
+            self.restore_bookmark_viewport();
+
+            return self.prefetch_pages();
+        }
+
+        let images: Vec<(String, iced::image::Handle)> = file_names
+            .iter()
+            .filter_map(|file_name| {
+                self.page_cache
+                    .get(file_name)
+                    .map(|handle| (file_name.clone(), handle))
+            })
+            .collect();
+
+        if images.len() == file_names.len() {
+            self.is_loading_page = false;
+            self.error = None;
+            self.current_page_view = Some(PageView::new(images, self.fit_mode));
+            self.restore_bookmark_viewport();
+        } else {
+            self.is_loading_page = true;
+            self.error = None;
+            self.current_page_view = None;
+        }
+
+        self.prefetch_pages()
+    }
+
+    /// Sizes the shown pages a different way, which also undoes any zoom,
+    /// and remembers the choice for the open comic.
+    fn set_fit_mode(&mut self, fit_mode: FitMode) -> Command<Message> {
+        self.fit_mode = fit_mode;
+
+        if let Some(page_view) = &mut self.current_page_view {
+            page_view.for_each_viewer(|viewer| viewer.set_fit_mode(fit_mode));
+        }
+
+        if let Some(current_comic) = &self.current_comic {
+            self.comic_settings.set(
+                &current_comic.path,
+                ComicSettings {
+                    fit_mode: Some(fit_mode),
+                },
+            );
+        }
+
+        self.redraw_scalable_pages()
+    }
+
+    fn prefetch_pages(&mut self) -> Command<Message> {
+        let current = self.current_page_index;
+
+        let (first, last) = if self.continuous_mode {
+            let on_screen = self.strip_pages_on_screen();
+
+            (
+                on_screen.start.saturating_sub(page_cache::PREFETCH_BEHIND),
+                on_screen.end.saturating_sub(1) + page_cache::PREFETCH_AHEAD,
+            )
+        } else {
+            // Spreads turn two pages at a time.
+            let pages_per_turn = if self.spread_mode { 2 } else { 1 };
+
+            (
+                current.saturating_sub(page_cache::PREFETCH_BEHIND * pages_per_turn),
+                current + page_cache::PREFETCH_AHEAD * pages_per_turn + pages_per_turn - 1,
+            )
+        };
+
+        let render_size = self.render_size(1.0);
+
+        let comic = match &self.current_comic {
+            Some(comic) => comic,
+            None => return Command::none(),
+        };
+
+        let last = last.min(comic.pages.len().saturating_sub(1));
+
+        // The current page goes first, then outwards in reading direction.
+        let indices = std::iter::once(current)
+            .chain(current + 1..=last)
+            .chain((first..current).rev());
+
+        let mut commands = Vec::new();
+
+        for index in indices {
+            let page = match comic.pages.get(index) {
+                Some(page) => page,
+                None => continue,
+            };
+
+            if self.page_cache.is_requested(page.file_name()) {
+                continue;
+            }
+
+            self.page_cache.mark_pending(page.file_name());
+
+            commands.push(decode_page(page, render_size, self.comic_generation));
+        }
+
+        // Cached scalable pages may have been drawn for a smaller view.
+        commands.push(self.redraw_scalable_pages());
+        commands.push(self.load_page_thumbnails());
+
+        Command::batch(commands)
+    }
+
+    /// The box a scalable page on screen is drawn to fit at the given zoom.
+    fn render_size(&self, zoom: f32) -> RenderSize {
+        let pages_across = if self.spread_mode && !self.continuous_mode {
+            2
+        } else {
+            1
+        };
+
+        let width = ((self.page_area_width() / pages_across) as f32 * zoom).ceil() as u32;
+        let height = (self.page_area_height() as f32 * zoom).ceil() as u32;
+
+        match self.fit_mode {
+            // The strip is always as wide as the window.
+            _ if self.continuous_mode => RenderSize {
+                width,
+                height: u32::MAX,
+            },
+            FitMode::Width => RenderSize {
+                width,
+                height: u32::MAX,
+            },
+            FitMode::Height => RenderSize {
+                width: u32::MAX,
+                height,
             },
+            _ => RenderSize { width, height },
+        }
+    }
+
+    /// Draws scalable pages on screen again if they are now shown bigger than
+    /// they were drawn for. Pages shown smaller are left as they are.
+    fn redraw_scalable_pages(&mut self) -> Command<Message> {
+        let zoomed_pages: Vec<(String, f32)> = match &self.current_page_view {
+            _ if self.continuous_mode => self
+                .strip_pages_on_screen()
+                .filter_map(|index| {
+                    let comic = self.current_comic.as_ref()?;
+                    Some((comic.pages.get(index)?.file_name().to_string(), 1.0))
+                })
+                .collect(),
+            Some(page_view) => page_view
+                .images
+                .iter()
+                .map(|image| (image.file_name.clone(), image.image_viewer.scale()))
+                .collect(),
+            None => Vec::new(),
         };
 
-        Container::new(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .center_x()
-            .center_y()
+        let mut commands = Vec::new();
+
+        for (file_name, zoom) in zoomed_pages {
+            let render_size = self.render_size(zoom);
+
+            let page = match self.current_comic.as_ref().and_then(|comic| {
+                comic
+                    .pages
+                    .iter()
+                    .find(|page| page.file_name() == file_name)
+            }) {
+                Some(page) if page.is_scalable() => page,
+                _ => continue,
+            };
+
+            let is_big_enough = self
+                .page_cache
+                .render_size(&file_name)
+                .map_or(false, |drawn| {
+                    drawn.width >= render_size.width && drawn.height >= render_size.height
+                });
+
+            if is_big_enough || self.page_cache.is_pending(&file_name) {
+                continue;
+            }
+
+            self.page_cache.mark_pending(&file_name);
+
+            commands.push(decode_page(page, render_size, self.comic_generation));
+        }
+
+        // Every change to what is on screen ends up here, zooming included.
+        commands.push(self.refresh_hud());
+
+        Command::batch(commands)
+    }
+
+    fn strip_layout(&self) -> StripLayout {
+        let pages = match &self.current_comic {
+            Some(comic) => &comic.pages,
+            None => return StripLayout::default(),
+        };
+
+        StripLayout::new(self.page_area_width() as f32, pages.len(), |index| {
+            self.page_sizes.get(pages[index].file_name()).copied()
+        })
+    }
+
+    fn current_strip_page_top(&self) -> Option<f32> {
+        self.strip_layout()
+            .page(self.current_page_index)
+            .map(|page| page.y)
+    }
+
+    /// Indices of the pages at least partly inside the window in continuous mode.
+    fn strip_pages_on_screen(&self) -> Range<usize> {
+        self.strip_layout().pages_between(
+            self.strip_offset,
+            self.strip_offset + self.page_area_height() as f32,
+        )
+    }
+
+    /// Scrolls the strip, keeping it inside the comic, and makes the page
+    /// across the middle of the window the current one.
+    fn scroll_strip_to(&mut self, offset: f32) -> Command<Message> {
+        let layout = self.strip_layout();
+        let visible_height = self.page_area_height() as f32;
+
+        let max_offset = (layout.height() - visible_height).max(0.0);
+        self.strip_offset = offset.min(max_offset).max(0.0);
+        self.current_page_index = layout.page_at(self.strip_offset + visible_height / 2.0);
+
+        self.prefetch_pages()
+    }
+
+    fn page_strip<'a>(&self) -> Element<'a, Message> {
+        let comic = match &self.current_comic {
+            Some(comic) => comic,
+            None => return Text::new("No Comic Loaded").into(),
+        };
+
+        let layout = self.strip_layout();
+
+        // Pages away from the window aren't part of the strip at all.
+        self.strip_pages_on_screen()
+            .fold(
+                PageStrip::new(self.strip_offset).on_scroll(Message::StripScrolled),
+                |strip, index| {
+                    let bounds = match layout.page(index) {
+                        Some(bounds) => bounds,
+                        None => return strip,
+                    };
+
+                    match self.page_cache.peek(comic.pages[index].file_name()) {
+                        Some(handle) => strip.push(
+                            bounds,
+                            Image::new(handle).width(Length::Fill).height(Length::Fill),
+                        ),
+                        None => strip.push(
+                            bounds,
+                            Container::new(Text::new("Loading Page"))
+                                .width(Length::Fill)
+                                .height(Length::Fill)
+                                .center_x()
+                                .center_y(),
+                        ),
+                    }
+                },
+            )
             .into()
     }
+
+    /// Whether something is shown over the comic that takes the keyboard and
+    /// mouse for itself.
+    fn is_overlay_open(&self) -> bool {
+        self.file_browser.is_some()
+            || self.library_view.is_some()
+            || self.go_to_page_dialog.is_some()
+    }
+
+    /// Shows the cover grid and looks for comics added or changed since the
+    /// library was last scanned.
+    fn open_library(&mut self) -> Command<Message> {
+        if self.library.is_none() {
+            match Library::open() {
+                Ok(library) => self.library = Some(library),
+                Err(error) => {
+                    eprintln!("library: {}", error);
+
+                    let mut library_view = LibraryView::new(Vec::new());
+                    library_view.set_error(Some(error.to_string()));
+                    self.library_view = Some(library_view);
+
+                    return Command::none();
+                }
+            }
+        }
+
+        let entries = self
+            .library
+            .as_ref()
+            .map_or(Ok(Vec::new()), Library::entries);
+
+        let mut library_view = LibraryView::new(Vec::new());
+
+        match entries {
+            Ok(entries) => library_view.set_entries(entries),
+            Err(error) => library_view.set_error(Some(error.to_string())),
+        }
+
+        self.library_view = Some(library_view);
+
+        Command::batch(vec![self.scan_library(), self.load_covers()])
+    }
+
+    fn scan_library(&mut self) -> Command<Message> {
+        if self.is_scanning_library {
+            return Command::none();
+        }
+
+        let stamps = match self.library.as_ref().map(Library::stamps) {
+            Some(Ok(stamps)) => stamps,
+            Some(Err(error)) => {
+                eprintln!("library: {}", error);
+                return Command::none();
+            }
+            None => return Command::none(),
+        };
+
+        self.is_scanning_library = true;
+
+        if let Some(library_view) = &mut self.library_view {
+            library_view.set_scanning(true);
+        }
+
+        Command::perform(
+            library::scan(Library::roots(), stamps),
+            Message::LibraryScanned,
+        )
+    }
+
+    fn load_covers(&mut self) -> Command<Message> {
+        Command::batch(
+            (0..COVER_LOADS_AT_ONCE)
+                .map(|_| self.load_next_cover())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn load_next_cover(&mut self) -> Command<Message> {
+        let path = match self
+            .library_view
+            .as_mut()
+            .and_then(LibraryView::next_cover_to_load)
+        {
+            Some(path) => path,
+            None => return Command::none(),
+        };
+
+        Command::perform(library::load_cover(path.clone()), move |result| {
+            Message::CoverLoaded {
+                path: path.clone(),
+                cover: result.ok(),
+            }
+        })
+    }
+
+    /// Moves the page navigator along with the reader and starts decoding the
+    /// thumbnails it is missing.
+    fn load_page_thumbnails(&mut self) -> Command<Message> {
+        let current = self.current_page_group().start;
+        let width = self.window_width;
+        let generation = self.comic_generation;
+
+        let (page_navigator, comic) = match (&mut self.page_navigator, &self.current_comic) {
+            (Some(page_navigator), Some(comic)) => (page_navigator, comic),
+            _ => return Command::none(),
+        };
+
+        page_navigator.follow(current, comic.pages.len(), width);
+
+        let mut commands = Vec::new();
+
+        while let Some(page) = page_navigator.next_thumbnail_to_load(&comic.pages, width) {
+            let file_name = page.file_name().to_string();
+
+            commands.push(Command::perform(
+                page_navigator::make_thumbnail(page.clone()),
+                move |result| Message::PageThumbnailLoaded {
+                    generation,
+                    file_name: file_name.clone(),
+                    thumbnail: result.ok(),
+                },
+            ));
+        }
+
+        Command::batch(commands)
+    }
+
+    /// What the HUD says: where the reader is in the comic and how it is shown.
+    fn hud_text(&self) -> Option<String> {
+        let comic = self.current_comic.as_ref()?;
+        let group = self.current_page_group();
+        let page = comic.pages.get(group.start)?;
+
+        let position = if group.len() > 1 {
+            format!(
+                "Pages {}-{} of {}",
+                group.start + 1,
+                group.end,
+                comic.pages.len()
+            )
+        } else {
+            format!("Page {} of {}", group.start + 1, comic.pages.len())
+        };
+
+        let order = comic.page_order.label();
+        let direction = if self.right_to_left {
+            ", right to left"
+        } else {
+            ""
+        };
+
+        // The strip is always as wide as the window and can't be zoomed.
+        if self.continuous_mode {
+            return Some(format!(
+                "{}  |  {}  |  {}  |  Continuous{}",
+                position,
+                page.file_name(),
+                order,
+                direction
+            ));
+        }
+
+        let fit = match self.fit_mode {
+            FitMode::Best => "Fit page",
+            FitMode::Width => "Fit width",
+            FitMode::Height => "Fit height",
+            FitMode::ActualSize => "Actual size",
+            FitMode::Stretch => "Stretch",
+        };
+
+        let zoom = self
+            .current_page_view
+            .as_ref()
+            .and_then(|page_view| page_view.images.first())
+            .map_or(1.0, |image| image.image_viewer.scale());
+
+        let mode = if self.spread_mode {
+            "Spread"
+        } else {
+            "Single page"
+        };
+
+        Some(format!(
+            "{}  |  {}  |  {}  |  {} {:.0}%  |  {}{}",
+            position,
+            page.file_name(),
+            order,
+            fit,
+            zoom * 100.0,
+            mode,
+            direction
+        ))
+    }
+
+    /// Brings up the HUD if what it says has changed since it last came up.
+    fn refresh_hud(&mut self) -> Command<Message> {
+        let hud_text = self.hud_text();
+
+        if hud_text == self.shown_hud_text {
+            return Command::none();
+        }
+
+        self.shown_hud_text = hud_text;
+
+        match self.shown_hud_text {
+            Some(_) => self.show_hud(),
+            None => Command::none(),
+        }
+    }
+
+    /// Brings up the HUD and hides it again after a moment.
+    fn show_hud(&mut self) -> Command<Message> {
+        self.is_hud_shown = true;
+        self.hud_generation += 1;
+
+        let generation = self.hud_generation;
+
+        Command::perform(async_std::task::sleep(hud::HUD_TIMEOUT), move |_| {
+            Message::HideHud { generation }
+        })
+    }
+
+    /// How much of the window's width the pages get.
+    fn page_area_width(&self) -> u32 {
+        if self.current_comic.is_none() {
+            return self.window_width;
+        }
+
+        let mut width = self.window_width;
+
+        if self.show_info {
+            width = width.saturating_sub(INFO_PANEL_WIDTH);
+        }
+
+        if self.bookmarks_panel.is_some() {
+            width = width.saturating_sub(BOOKMARKS_PANEL_WIDTH);
+        }
+
+        width
+    }
+
+    /// How much of the window's height the pages get.
+    fn page_area_height(&self) -> u32 {
+        if self.page_navigator.is_some() && self.current_comic.is_some() {
+            self.window_height.saturating_sub(NAVIGATOR_HEIGHT)
+        } else {
+            self.window_height
+        }
+    }
+
+    /// What is shown in place of the pages while there are none to show.
+    fn status_message<'a>(&self) -> Row<'a, Message> {
+        match (&self.error, self.is_opening) {
+            (_, true) => Row::new()
+                .width(Length::Shrink)
+                .push(Text::new("Loading Comic File")),
+            (None, false) if self.is_loading_page => Row::new()
+                .width(Length::Shrink)
+                .push(Text::new("Loading Page")),
+            // The comic is still open, only this page is broken.
+            (Some(error), false) if self.current_comic.is_some() => {
+                Row::new().width(Length::Shrink).push(
+                    Column::new()
+                        .spacing(10)
+                        .push(Text::new(format!("Could not show this page: {}", error)))
+                        .push(Text::new("Turn the page to keep reading").size(16)),
+                )
+            }
+            (Some(error), false) => Row::new().width(Length::Shrink).push(
+                Column::new()
+                    .spacing(10)
+                    .push(Text::new(format!("Could not open comic: {}", error)))
+                    .push(Text::new("Drop another file to try again").size(16)),
+            ),
+            (None, false) => Row::new()
+                .width(Length::Shrink)
+                .push(self.keymap_errors.iter().fold(
+                    Column::new().push(Text::new("No Comic Loaded")),
+                    |column, error| column.push(Text::new(format!("keymap: {}", error)).size(16)),
+                )),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct PageView {
+    /// The pages on screen, from left to right.
+    images: Vec<PageImage>,
+}
+
+#[derive(Debug, Clone)]
+struct PageImage {
+    file_name: String,
     image_viewer: image_viewer::ImageViewerState,
     img_data: iced::image::Handle,
 }
 
 impl PageView {
     fn view(&mut self) -> Element<Message> {
-        Row::new()
-            .height(Length::Fill)
-            .width(Length::Fill)
-            .push(
-                image_viewer::ImageViewer::new(&mut self.image_viewer, self.img_data.clone())
-                    .width(Length::Fill)
-                    .height(Length::Fill),
+        let is_spread = self.images.len() > 1;
+        let last_index = self.images.len().saturating_sub(1);
+
+        self.images
+            .iter_mut()
+            .enumerate()
+            .fold(
+                Row::new().height(Length::Fill).width(Length::Fill),
+                |row, (index, image)| {
+                    // Pages of a spread meet in the middle like in the printed book.
+                    let alignment = match (is_spread, index) {
+                        (false, _) => Align::Center,
+                        (true, 0) => Align::End,
+                        (true, index) if index == last_index => Align::Start,
+                        (true, _) => Align::Center,
+                    };
+
+                    row.push(
+                        image_viewer::ImageViewer::new(
+                            &mut image.image_viewer,
+                            image.img_data.clone(),
+                        )
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .align_x(alignment),
+                    )
+                },
             )
             .into()
     }
 
-    fn open_page(page: Page) -> Result<PageView> {
-        let img_data = iced::image::Handle::from_memory(page.as_bytes().unwrap());
-        let image_viewer = image_viewer::ImageViewerState::new();
+    fn new(images: Vec<(String, iced::image::Handle)>, fit_mode: FitMode) -> Self {
+        Self {
+            images: images
+                .into_iter()
+                .map(|(file_name, img_data)| PageImage {
+                    file_name,
+                    image_viewer: image_viewer::ImageViewerState::with_fit_mode(fit_mode),
+                    img_data,
+                })
+                .collect(),
+        }
+    }
 
-        Ok(Self {
-            image_viewer,
-            img_data,
-        })
+    fn file_names(&self) -> Vec<&str> {
+        self.images
+            .iter()
+            .map(|image| image.file_name.as_str())
+            .collect()
+    }
+
+    fn replace_image(&mut self, file_name: &str, handle: iced::image::Handle) {
+        for image in &mut self.images {
+            if image.file_name == file_name {
+                image.img_data = handle.clone();
+            }
+        }
+    }
+
+    fn for_each_viewer(&mut self, f: impl Fn(&mut image_viewer::ImageViewerState)) {
+        for image in &mut self.images {
+            f(&mut image.image_viewer);
+        }
     }
 }
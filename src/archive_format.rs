@@ -0,0 +1,278 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// Enough of the start of a file to find any of the signatures below, the
+/// tar one sits furthest in.
+const SIGNATURE_LENGTH: u64 = 512;
+
+/// Offset of the `ustar` magic inside a tar header.
+const TAR_MAGIC_OFFSET: usize = 257;
+
+/// An EPUB is a zip whose first entry is an uncompressed `mimetype` file,
+/// so its name and contents sit right after the first local file header.
+const EPUB_MIMETYPE_OFFSET: usize = 30;
+const EPUB_MIMETYPE: &[u8] = b"mimetypeapplication/epub+zip";
+
+/// The container formats a comic can come in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Rar,
+    SevenZip,
+    Tar,
+    /// A gzip stream, which says nothing about what was compressed.
+    Gzip,
+    /// A zstd stream, which says nothing about what was compressed.
+    Zstd,
+    Pdf,
+    /// A zip laid out as an EPUB book.
+    Epub,
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArchiveFormat::Zip => "zip archive",
+            ArchiveFormat::Rar => "RAR archive",
+            ArchiveFormat::SevenZip => "7z archive",
+            ArchiveFormat::Tar => "tar archive",
+            ArchiveFormat::Gzip => "gzip file",
+            ArchiveFormat::Zstd => "zstd file",
+            ArchiveFormat::Pdf => "PDF",
+            ArchiveFormat::Epub => "EPUB",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl ArchiveFormat {
+    /// Recognizes a format from the first bytes of a file.
+    pub fn sniff(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PK\x03\x04")
+            && head.get(EPUB_MIMETYPE_OFFSET..EPUB_MIMETYPE_OFFSET + EPUB_MIMETYPE.len())
+                == Some(EPUB_MIMETYPE)
+        {
+            return Some(ArchiveFormat::Epub);
+        }
+
+        // Empty and spanned zips start with a different record than the usual
+        // local file header, but are still zips.
+        if head.starts_with(b"PK\x03\x04")
+            || head.starts_with(b"PK\x05\x06")
+            || head.starts_with(b"PK\x07\x08")
+        {
+            return Some(ArchiveFormat::Zip);
+        }
+
+        // RAR 1.5 to 4 and RAR 5 share the first six bytes.
+        if head.starts_with(b"Rar!\x1a\x07") {
+            return Some(ArchiveFormat::Rar);
+        }
+
+        if head.starts_with(b"7z\xbc\xaf\x27\x1c") {
+            return Some(ArchiveFormat::SevenZip);
+        }
+
+        if head.starts_with(b"\x1f\x8b") {
+            return Some(ArchiveFormat::Gzip);
+        }
+
+        if head.starts_with(b"\x28\xb5\x2f\xfd") {
+            return Some(ArchiveFormat::Zstd);
+        }
+
+        if head.starts_with(b"%PDF-") {
+            return Some(ArchiveFormat::Pdf);
+        }
+
+        if head.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5) == Some(b"ustar") {
+            return Some(ArchiveFormat::Tar);
+        }
+
+        None
+    }
+
+    /// The format a file's name claims it has.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "zip" | "cbz" => Some(ArchiveFormat::Zip),
+            "rar" | "cbr" => Some(ArchiveFormat::Rar),
+            "7z" | "cb7" => Some(ArchiveFormat::SevenZip),
+            "tar" | "cbt" => Some(ArchiveFormat::Tar),
+            "gz" | "tgz" => Some(ArchiveFormat::Gzip),
+            "zst" | "tzst" => Some(ArchiveFormat::Zstd),
+            "pdf" => Some(ArchiveFormat::Pdf),
+            "epub" => Some(ArchiveFormat::Epub),
+            _ => None,
+        }
+    }
+
+    /// Settles on a format from what a file's name claims and what its first
+    /// bytes say. The content wins whenever it says anything: a `.cbz` that
+    /// is really a RAR is opened as a RAR, and a `.cbt` that is really
+    /// gzipped as a compressed tarball. Only tar files written by old tools
+    /// carry no magic and need the name to say what they are.
+    pub fn resolve(claimed: Option<Self>, sniffed: Option<Self>) -> Option<Self> {
+        match (claimed, sniffed) {
+            // Not every EPUB writer stores the mimetype the way the signature
+            // expects, a zip named `.epub` is taken at its word.
+            (Some(ArchiveFormat::Epub), Some(ArchiveFormat::Zip)) => Some(ArchiveFormat::Epub),
+            (_, Some(sniffed)) => Some(sniffed),
+            (claimed, None) => claimed,
+        }
+    }
+
+    /// Whether a file named like this format but really `actual` is worth a
+    /// warning. A tarball compressed without its name saying so still opens
+    /// as the tarball it claims to be.
+    pub fn is_misnamed_as(self, actual: Self) -> bool {
+        let is_compressed_tar = self == ArchiveFormat::Tar
+            && matches!(actual, ArchiveFormat::Gzip | ArchiveFormat::Zstd);
+
+        self != actual && !is_compressed_tar
+    }
+
+    /// Reads the start of the file at `path` and recognizes its format.
+    pub fn sniff_file(path: &Path) -> io::Result<Option<Self>> {
+        let mut head = Vec::new();
+        File::open(path)?
+            .take(SIGNATURE_LENGTH)
+            .read_to_end(&mut head)?;
+
+        Ok(ArchiveFormat::sniff(&head))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_header() -> Vec<u8> {
+        let mut head = vec![0; 512];
+        head[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 6].copy_from_slice(b"ustar\0");
+
+        head
+    }
+
+    #[test]
+    fn sniffs_signatures() {
+        assert_eq!(
+            ArchiveFormat::sniff(b"PK\x03\x04rest"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"PK\x05\x06"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"Rar!\x1a\x07\x00"),
+            Some(ArchiveFormat::Rar)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"Rar!\x1a\x07\x01\x00"),
+            Some(ArchiveFormat::Rar)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"7z\xbc\xaf\x27\x1c"),
+            Some(ArchiveFormat::SevenZip)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"\x1f\x8b\x08"),
+            Some(ArchiveFormat::Gzip)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"\x28\xb5\x2f\xfd"),
+            Some(ArchiveFormat::Zstd)
+        );
+        assert_eq!(ArchiveFormat::sniff(b"%PDF-1.7"), Some(ArchiveFormat::Pdf));
+        assert_eq!(
+            ArchiveFormat::sniff(&tar_header()),
+            Some(ArchiveFormat::Tar)
+        );
+    }
+
+    #[test]
+    fn sniffs_an_epub_by_its_mimetype_entry() {
+        let mut head = b"PK\x03\x04".to_vec();
+        head.resize(EPUB_MIMETYPE_OFFSET, 0);
+        head.extend_from_slice(EPUB_MIMETYPE);
+
+        assert_eq!(ArchiveFormat::sniff(&head), Some(ArchiveFormat::Epub));
+    }
+
+    #[test]
+    fn does_not_sniff_unknown_or_short_heads() {
+        assert_eq!(ArchiveFormat::sniff(b""), None);
+        assert_eq!(ArchiveFormat::sniff(b"PK"), None);
+        assert_eq!(ArchiveFormat::sniff(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(ArchiveFormat::sniff(&[0; 512]), None);
+    }
+
+    #[test]
+    fn reads_the_extension_in_any_case() {
+        let format = |name: &str| ArchiveFormat::from_extension(Path::new(name));
+
+        assert_eq!(format("Issue 1.CBZ"), Some(ArchiveFormat::Zip));
+        assert_eq!(format("issue.cbr"), Some(ArchiveFormat::Rar));
+        assert_eq!(format("issue.Cb7"), Some(ArchiveFormat::SevenZip));
+        assert_eq!(format("issue.cbt"), Some(ArchiveFormat::Tar));
+        assert_eq!(format("issue.tar.gz"), Some(ArchiveFormat::Gzip));
+        assert_eq!(format("issue.tzst"), Some(ArchiveFormat::Zstd));
+        assert_eq!(format("issue.pdf"), Some(ArchiveFormat::Pdf));
+        assert_eq!(format("issue.epub"), Some(ArchiveFormat::Epub));
+        assert_eq!(format("issue.txt"), None);
+        assert_eq!(format("issue"), None);
+    }
+
+    #[test]
+    fn content_wins_over_the_name() {
+        assert_eq!(
+            ArchiveFormat::resolve(Some(ArchiveFormat::Zip), Some(ArchiveFormat::Rar)),
+            Some(ArchiveFormat::Rar)
+        );
+    }
+
+    #[test]
+    fn compression_wins_over_a_tar_name() {
+        assert_eq!(
+            ArchiveFormat::resolve(Some(ArchiveFormat::Tar), Some(ArchiveFormat::Gzip)),
+            Some(ArchiveFormat::Gzip)
+        );
+        assert_eq!(
+            ArchiveFormat::resolve(Some(ArchiveFormat::Tar), Some(ArchiveFormat::Zstd)),
+            Some(ArchiveFormat::Zstd)
+        );
+    }
+
+    #[test]
+    fn the_name_is_used_when_the_content_says_nothing() {
+        assert_eq!(
+            ArchiveFormat::resolve(Some(ArchiveFormat::Tar), None),
+            Some(ArchiveFormat::Tar)
+        );
+        assert_eq!(ArchiveFormat::resolve(None, None), None);
+    }
+
+    #[test]
+    fn a_zip_named_epub_is_an_epub() {
+        assert_eq!(
+            ArchiveFormat::resolve(Some(ArchiveFormat::Epub), Some(ArchiveFormat::Zip)),
+            Some(ArchiveFormat::Epub)
+        );
+    }
+
+    #[test]
+    fn a_compressed_tarball_named_tar_is_not_misnamed() {
+        assert!(!ArchiveFormat::Tar.is_misnamed_as(ArchiveFormat::Gzip));
+        assert!(!ArchiveFormat::Zip.is_misnamed_as(ArchiveFormat::Zip));
+        assert!(ArchiveFormat::Zip.is_misnamed_as(ArchiveFormat::Rar));
+        assert!(ArchiveFormat::Gzip.is_misnamed_as(ArchiveFormat::Tar));
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use iced::Rectangle;
+use serde_json::{json, Map, Value};
+
+const BOOKMARKS_FILE_NAME: &str = "bookmarks.json";
+const EXPORT_SUFFIX: &str = ".bookmarks.json";
+
+/// How a page was zoomed and panned when it was bookmarked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Zoom relative to the fitted size.
+    pub zoom: f32,
+    /// The part of the page in view, in fractions of its width and height
+    /// from its top left corner.
+    pub region: Rectangle,
+}
+
+/// A page worth coming back to, such as a panel to talk about in a review.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    /// Index of the page when it was bookmarked, counted from zero.
+    pub page: usize,
+    /// File name of the page, to find it again when the pages are in a
+    /// different order.
+    pub file_name: Option<String>,
+    pub viewport: Option<Viewport>,
+    pub label: String,
+    pub note: String,
+}
+
+impl Bookmark {
+    /// The bookmark as it is written out, with the page counted from 1 like
+    /// everywhere a person reads it.
+    fn to_json(&self) -> Value {
+        let mut bookmark = json!({
+            "page": self.page + 1,
+            "label": self.label,
+            "note": self.note,
+        });
+
+        if let Some(file_name) = &self.file_name {
+            bookmark["file_name"] = json!(file_name);
+        }
+
+        if let Some(viewport) = &self.viewport {
+            bookmark["viewport"] = json!({
+                "zoom": viewport.zoom,
+                "x": viewport.region.x,
+                "y": viewport.region.y,
+                "width": viewport.region.width,
+                "height": viewport.region.height,
+            });
+        }
+
+        bookmark
+    }
+
+    /// Reads a bookmark written by [`Bookmark::to_json`], or `None` if it
+    /// doesn't say which page it is for.
+    fn from_json(value: &Value) -> Option<Self> {
+        let page = value.get("page")?.as_u64()?.checked_sub(1)? as usize;
+        let text = |name: &str| value.get(name).and_then(Value::as_str).map(str::to_string);
+
+        let viewport = value.get("viewport").and_then(|viewport| {
+            let number = |name: &str| {
+                viewport
+                    .get(name)
+                    .and_then(Value::as_f64)
+                    .map(|number| number as f32)
+                    .filter(|number| number.is_finite())
+            };
+
+            let fraction = |name: &str| number(name).map(|number| number.clamp(0.0, 1.0));
+
+            Some(Viewport {
+                zoom: number("zoom")?,
+                region: Rectangle {
+                    x: fraction("x")?,
+                    y: fraction("y")?,
+                    width: fraction("width")?,
+                    height: fraction("height")?,
+                },
+            })
+        });
+
+        Some(Self {
+            page,
+            file_name: text("file_name"),
+            viewport,
+            label: text("label").unwrap_or_default(),
+            note: text("note").unwrap_or_default(),
+        })
+    }
+}
+
+/// Bookmarks for every comic that has any, keyed by its path, in page order.
+///
+/// Like the comic settings, a missing or broken file just means starting
+/// without bookmarks.
+#[derive(Debug, Clone, Default)]
+pub struct BookmarkStore {
+    comics: HashMap<String, Vec<Bookmark>>,
+}
+
+impl BookmarkStore {
+    pub fn data_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("comik").join(BOOKMARKS_FILE_NAME))
+    }
+
+    pub fn load() -> Self {
+        let comics = BookmarkStore::data_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| match serde_json::from_str::<Value>(&contents) {
+                Ok(Value::Object(comics)) => Some(comics),
+                Ok(_) => None,
+                Err(error) => {
+                    eprintln!("bookmarks: {}", error);
+                    None
+                }
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(path, bookmarks)| (path, bookmarks_from_json(&bookmarks)))
+            .collect();
+
+        Self { comics }
+    }
+
+    pub fn get(&self, comic_path: &Path) -> &[Bookmark] {
+        self.comics.get(&key(comic_path)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds a bookmark after any others for the same page.
+    pub fn add(&mut self, comic_path: &Path, bookmark: Bookmark) {
+        self.insert(comic_path, bookmark);
+        self.save_or_complain();
+    }
+
+    pub fn remove(&mut self, comic_path: &Path, index: usize) {
+        if let Some(bookmarks) = self.comics.get_mut(&key(comic_path)) {
+            if index < bookmarks.len() {
+                bookmarks.remove(index);
+            }
+
+            if bookmarks.is_empty() {
+                self.comics.remove(&key(comic_path));
+            }
+        }
+
+        self.save_or_complain();
+    }
+
+    /// Adds the bookmarks that aren't there yet, such as ones shared by
+    /// someone else, and returns how many that was.
+    pub fn merge(&mut self, comic_path: &Path, bookmarks: Vec<Bookmark>) -> usize {
+        let mut added = 0;
+
+        for bookmark in bookmarks {
+            if !self.get(comic_path).contains(&bookmark) {
+                self.insert(comic_path, bookmark);
+                added += 1;
+            }
+        }
+
+        // Written out once for the lot rather than once for each.
+        if added > 0 {
+            self.save_or_complain();
+        }
+
+        added
+    }
+
+    fn insert(&mut self, comic_path: &Path, bookmark: Bookmark) {
+        let bookmarks = self.comics.entry(key(comic_path)).or_default();
+        let position = bookmarks
+            .iter()
+            .take_while(|other| other.page <= bookmark.page)
+            .count();
+
+        bookmarks.insert(position, bookmark);
+    }
+
+    fn save_or_complain(&self) {
+        if let Err(error) = self.save() {
+            eprintln!("bookmarks: could not save: {}", error);
+        }
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let path = match BookmarkStore::data_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let comics: Map<String, Value> = self
+            .comics
+            .iter()
+            .map(|(path, bookmarks)| (path.clone(), bookmarks_to_json(bookmarks)))
+            .collect();
+
+        fs::write(&path, serde_json::to_string_pretty(&comics)?)?;
+
+        Ok(())
+    }
+}
+
+/// Where the bookmarks of a comic are exported to unless told otherwise,
+/// next to the comic, so they travel with it.
+pub fn export_path(comic_path: &Path) -> PathBuf {
+    let mut path = OsString::from(comic_path);
+    path.push(EXPORT_SUFFIX);
+
+    PathBuf::from(path)
+}
+
+/// Bookmarks read back from a file by [`import`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedBookmarks {
+    pub bookmarks: Vec<Bookmark>,
+    /// The file name of the comic they were exported from, when it isn't
+    /// the one they are being imported into. Renaming a comic is enough for
+    /// that, so it is only worth a warning.
+    pub other_comic: Option<String>,
+}
+
+/// Writes bookmarks to a file to share them. The comic's file name goes in
+/// too, to tell what they are for.
+pub fn export(path: &Path, comic_path: &Path, bookmarks: &[Bookmark]) -> anyhow::Result<()> {
+    let comic = comic_file_name(comic_path);
+
+    let document = json!({
+        "comic": comic,
+        "bookmarks": bookmarks_to_json(bookmarks),
+    });
+
+    fs::write(path, serde_json::to_string_pretty(&document)?)?;
+
+    Ok(())
+}
+
+/// Reads bookmarks written by [`export`] for the comic at `comic_path`.
+/// Bookmarks that can't be made sense of are left out rather than failing
+/// the rest.
+pub fn import(path: &Path, comic_path: &Path) -> anyhow::Result<ImportedBookmarks> {
+    let document: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+    let bookmarks = match document.get("bookmarks") {
+        Some(bookmarks) if bookmarks.is_array() => bookmarks_from_json(bookmarks),
+        _ => return Err(anyhow::anyhow!("{:?} has no bookmarks in it", path)),
+    };
+
+    let other_comic = document
+        .get("comic")
+        .and_then(Value::as_str)
+        .filter(|comic| Some(*comic) != comic_file_name(comic_path).as_deref())
+        .map(str::to_string);
+
+    Ok(ImportedBookmarks {
+        bookmarks,
+        other_comic,
+    })
+}
+
+fn comic_file_name(comic_path: &Path) -> Option<String> {
+    comic_path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+}
+
+fn bookmarks_to_json(bookmarks: &[Bookmark]) -> Value {
+    Value::Array(bookmarks.iter().map(Bookmark::to_json).collect())
+}
+
+fn bookmarks_from_json(bookmarks: &Value) -> Vec<Bookmark> {
+    let mut bookmarks: Vec<Bookmark> = bookmarks
+        .as_array()
+        .map(|bookmarks| bookmarks.iter().filter_map(Bookmark::from_json).collect())
+        .unwrap_or_default();
+
+    bookmarks.sort_by_key(|bookmark| bookmark.page);
+
+    bookmarks
+}
+
+fn key(comic_path: &Path) -> String {
+    comic_path.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmark_round_trips_through_json() {
+        let bookmark = Bookmark {
+            page: 4,
+            file_name: Some("05.png".to_string()),
+            viewport: Some(Viewport {
+                zoom: 2.5,
+                region: Rectangle {
+                    x: 0.25,
+                    y: 0.5,
+                    width: 0.5,
+                    height: 0.25,
+                },
+            }),
+            label: "The reveal".to_string(),
+            note: "Look at the background".to_string(),
+        };
+
+        assert_eq!(Bookmark::from_json(&bookmark.to_json()), Some(bookmark));
+    }
+
+    #[test]
+    fn bookmark_without_a_viewport_round_trips_through_json() {
+        let bookmark = Bookmark {
+            page: 0,
+            file_name: None,
+            viewport: None,
+            label: String::new(),
+            note: String::new(),
+        };
+
+        assert_eq!(Bookmark::from_json(&bookmark.to_json()), Some(bookmark));
+    }
+
+    #[test]
+    fn pages_are_written_counted_from_one() {
+        assert_eq!(Bookmark::from_json(&json!({ "page": 0 })), None);
+        assert_eq!(
+            Bookmark::from_json(&json!({ "page": 1 })).map(|bookmark| bookmark.page),
+            Some(0)
+        );
+    }
+}
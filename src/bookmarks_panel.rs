@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use iced::{
+    button, scrollable, text_input, Button, Column, Element, Length, Row, Scrollable, Text,
+    TextInput,
+};
+
+use crate::bookmarks::Bookmark;
+
+/// Width of the bookmarks panel, in pixels.
+pub const BOOKMARKS_PANEL_WIDTH: u32 = 320;
+
+#[derive(Debug, Clone)]
+pub enum BookmarksMessage {
+    LabelChanged(String),
+    NoteChanged(String),
+    /// Bookmark the page being read with the label and note typed in.
+    Add,
+    GoTo(usize),
+    Delete(usize),
+    PathChanged(String),
+    Export,
+    Import,
+    Close,
+}
+
+#[derive(Debug, Clone, Default)]
+struct BookmarkButtons {
+    go_to_button: button::State,
+    delete_button: button::State,
+}
+
+/// The bookmarks of the comic being read, next to the pages, with a form to
+/// add one for the current page and a file to share them through.
+#[derive(Debug, Clone, Default)]
+pub struct BookmarksPanel {
+    label: String,
+    label_input: text_input::State,
+    note: String,
+    note_input: text_input::State,
+    /// Where to export to and import from, the file next to the comic when
+    /// left empty.
+    path: String,
+    path_input: text_input::State,
+    /// What came of the last export or import.
+    status: Option<String>,
+    rows: Vec<BookmarkButtons>,
+    scroll: scrollable::State,
+    add_button: button::State,
+    export_button: button::State,
+    import_button: button::State,
+    close_button: button::State,
+}
+
+impl BookmarksPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_label(&mut self, label: String) {
+        self.label = label;
+    }
+
+    pub fn set_note(&mut self, note: String) {
+        self.note = note;
+    }
+
+    pub fn set_path(&mut self, path: String) {
+        self.path = path;
+    }
+
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    /// The label and note typed in, clearing them for the next bookmark.
+    pub fn take_input(&mut self) -> (String, String) {
+        (
+            std::mem::take(&mut self.label).trim().to_string(),
+            std::mem::take(&mut self.note).trim().to_string(),
+        )
+    }
+
+    /// The file to export to or import from, if one was typed in.
+    pub fn path(&self) -> Option<PathBuf> {
+        Some(self.path.trim())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Whether keys are going into one of the panel's inputs.
+    pub fn is_typing(&self) -> bool {
+        self.label_input.is_focused()
+            || self.note_input.is_focused()
+            || self.path_input.is_focused()
+    }
+
+    pub fn stop_typing(&mut self) {
+        self.label_input.unfocus();
+        self.note_input.unfocus();
+        self.path_input.unfocus();
+    }
+
+    /// The panel for `bookmarks`, with `default_path` shown as where they are
+    /// shared through until another file is typed in.
+    pub fn view(
+        &mut self,
+        bookmarks: &[Bookmark],
+        default_path: &str,
+    ) -> Element<'_, BookmarksMessage> {
+        let header = Row::new()
+            .spacing(10)
+            .push(Text::new("Bookmarks").size(24).width(Length::Fill))
+            .push(
+                Button::new(&mut self.close_button, Text::new("Close"))
+                    .on_press(BookmarksMessage::Close),
+            );
+
+        let form = Column::new()
+            .spacing(8)
+            .push(
+                TextInput::new(
+                    &mut self.label_input,
+                    "Label",
+                    &self.label,
+                    BookmarksMessage::LabelChanged,
+                )
+                .padding(6)
+                .on_submit(BookmarksMessage::Add),
+            )
+            .push(
+                TextInput::new(
+                    &mut self.note_input,
+                    "Note",
+                    &self.note,
+                    BookmarksMessage::NoteChanged,
+                )
+                .padding(6)
+                .on_submit(BookmarksMessage::Add),
+            )
+            .push(
+                Button::new(&mut self.add_button, Text::new("Bookmark This Page"))
+                    .on_press(BookmarksMessage::Add),
+            );
+
+        self.rows
+            .resize_with(bookmarks.len(), BookmarkButtons::default);
+
+        let mut list = Scrollable::new(&mut self.scroll)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .spacing(12);
+
+        if bookmarks.is_empty() {
+            list = list.push(Text::new("No bookmarks yet").size(16));
+        }
+
+        for (index, (bookmark, row)) in bookmarks.iter().zip(&mut self.rows).enumerate() {
+            let label = if bookmark.label.is_empty() {
+                format!("Page {}", bookmark.page + 1)
+            } else {
+                format!("{} - page {}", bookmark.label, bookmark.page + 1)
+            };
+
+            let mut details = Column::new().spacing(2).push(Text::new(label).size(16));
+
+            if !bookmark.note.is_empty() {
+                details = details.push(Text::new(bookmark.note.clone()).size(14));
+            }
+
+            if let Some(viewport) = &bookmark.viewport {
+                details = details
+                    .push(Text::new(format!("Zoomed to {:.0}%", viewport.zoom * 100.0)).size(12));
+            }
+
+            list = list.push(
+                Row::new()
+                    .spacing(6)
+                    .push(
+                        Button::new(&mut row.go_to_button, details)
+                            .width(Length::Fill)
+                            .on_press(BookmarksMessage::GoTo(index)),
+                    )
+                    .push(
+                        Button::new(&mut row.delete_button, Text::new("Delete"))
+                            .on_press(BookmarksMessage::Delete(index)),
+                    ),
+            );
+        }
+
+        let mut sharing = Column::new()
+            .spacing(8)
+            .push(
+                TextInput::new(
+                    &mut self.path_input,
+                    default_path,
+                    &self.path,
+                    BookmarksMessage::PathChanged,
+                )
+                .padding(6)
+                .size(14),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        Button::new(&mut self.export_button, Text::new("Export"))
+                            .on_press(BookmarksMessage::Export),
+                    )
+                    .push(
+                        Button::new(&mut self.import_button, Text::new("Import"))
+                            .on_press(BookmarksMessage::Import),
+                    ),
+            );
+
+        if let Some(status) = &self.status {
+            sharing = sharing.push(Text::new(status.clone()).size(14));
+        }
+
+        Column::new()
+            .width(Length::Units(BOOKMARKS_PANEL_WIDTH as u16))
+            .height(Length::Fill)
+            .padding(20)
+            .spacing(16)
+            .push(header)
+            .push(form)
+            .push(list)
+            .push(sharing)
+            .into()
+    }
+}
@@ -1,45 +1,318 @@
-use std::{cmp::Ordering, fs::File, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Result;
-use image::io::Reader as ImageReader;
-use image::{DynamicImage, ImageError, ImageOutputFormat};
+use image::DynamicImage;
+use sevenz_rust::{Error as SevenZError, Password, SevenZReader};
+use tempfile::TempDir;
 use thiserror::Error;
+use unrar::error::{Code as UnrarCode, UnrarError};
 use unrar::Archive as RarArchive;
-use zip::ZipArchive;
+use zip::{read::ZipFile, result::ZipError, ZipArchive};
+
+use crate::{
+    archive_format::ArchiveFormat,
+    comic_info::{ComicMetadata, PageType, COMIC_INFO_FILE_NAME},
+    epub, natural_sort, pdf,
+};
+
+const TEMP_DIRECTORY_PREFIX: &str = "comik-";
+
+/// Extensions of the archives a comic can be opened from.
+pub const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "cbz", "rar", "cbr", "7z", "cb7", "tar", "cbt", "gz", "tgz", "zst", "tzst", "pdf",
+    "epub",
+];
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "jpe", "png", "gif", "webp", "bmp", "tif", "tiff", "tga", "ico", "pnm",
+];
+
+const METADATA_EXTENSIONS: &[&str] = &["xml", "opf", "json", "acbf"];
+
+const TEXT_EXTENSIONS: &[&str] = &["txt", "nfo", "md", "sfv"];
+
+/// Files that archivers and file managers drop next to the real content.
+const IGNORED_FILE_NAMES: &[&str] = &["thumbs.db", "desktop.ini", ".ds_store"];
+
+/// Enough of the start of a file for `image::guess_format` to recognize it.
+const MAGIC_BYTES_LENGTH: u64 = 32;
 
 #[derive(Error, Debug, Clone)]
 pub enum ComicError {
     #[error("invalid archive type")]
     InvalidArchiveType,
+    #[error("could not read the file: {0}")]
+    Io(String),
+    #[error("the archive is corrupt: {0}")]
+    CorruptArchive(String),
+    #[error("the archive is password protected")]
+    EncryptedArchive,
+    #[error("the archive does not contain any pages")]
+    EmptyComic,
+    #[error("page {file_name} could not be decoded: {reason}")]
+    UndecodablePage { file_name: String, reason: String },
+}
+
+/// Something off about a comic that didn't stop it from opening, but that
+/// whoever made the file may want to know about.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComicWarning {
+    #[error("this file is named like a {claimed} but is actually a {actual}")]
+    Misnamed {
+        claimed: ArchiveFormat,
+        actual: ArchiveFormat,
+    },
+}
+
+impl From<io::Error> for ComicError {
+    fn from(error: io::Error) -> Self {
+        ComicError::Io(error.to_string())
+    }
+}
+
+impl From<ZipError> for ComicError {
+    fn from(error: ZipError) -> Self {
+        match error {
+            ZipError::Io(error) => error.into(),
+            ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+                ComicError::EncryptedArchive
+            }
+            ZipError::InvalidArchive(reason) | ZipError::UnsupportedArchive(reason) => {
+                ComicError::CorruptArchive(reason.to_string())
+            }
+            ZipError::FileNotFound => ComicError::CorruptArchive(error.to_string()),
+        }
+    }
+}
+
+impl<T> From<UnrarError<T>> for ComicError {
+    fn from(error: UnrarError<T>) -> Self {
+        match error.code {
+            UnrarCode::MissingPassword | UnrarCode::BadPassword => ComicError::EncryptedArchive,
+            UnrarCode::EOpen | UnrarCode::ERead | UnrarCode::ECreate | UnrarCode::EWrite => {
+                ComicError::Io(error.to_string())
+            }
+            _ => ComicError::CorruptArchive(error.to_string()),
+        }
+    }
+}
+
+impl From<SevenZError> for ComicError {
+    fn from(error: SevenZError) -> Self {
+        match error {
+            SevenZError::PasswordRequired | SevenZError::MaybeBadPassword(_) => {
+                ComicError::EncryptedArchive
+            }
+            SevenZError::Io(error, _) => error.into(),
+            error => ComicError::CorruptArchive(error.to_string()),
+        }
+    }
+}
+
+type SharedZipArchive = Arc<Mutex<ZipArchive<BufReader<File>>>>;
+
+fn open_zip(path: &Path) -> Result<SharedZipArchive, ComicError> {
+    let reader = BufReader::new(File::open(path)?);
+
+    Ok(Arc::new(Mutex::new(ZipArchive::new(reader)?)))
+}
+
+fn lock_archive(
+    archive: &SharedZipArchive,
+) -> io::Result<MutexGuard<'_, ZipArchive<BufReader<File>>>> {
+    archive
+        .lock()
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "zip archive lock poisoned"))
+}
+
+/// Where the bytes of a [`Page`] live.
+#[derive(Debug, Clone)]
+pub enum PageSource {
+    /// An entry of a zip archive that is kept open, read by index on demand.
+    ZipEntry {
+        archive: SharedZipArchive,
+        index: usize,
+    },
+    /// An entry of an uncompressed tar file, found at a byte offset that was
+    /// recorded when the archive was listed.
+    TarEntry {
+        archive: Arc<PathBuf>,
+        offset: u64,
+        size: u64,
+        /// Where a compressed tarball was decompressed to, if it was. Held
+        /// so the directory is only deleted once no page needs it anymore.
+        #[allow(dead_code)]
+        temp_directory: Option<Arc<TempDir>>,
+    },
+    /// A plain file on disk, used for archives that cannot seek to an entry
+    /// and have to be extracted up front.
+    File {
+        path: PathBuf,
+        /// Where the file was extracted to, if it was, held for the same
+        /// reason as above.
+        #[allow(dead_code)]
+        temp_directory: Option<Arc<TempDir>>,
+    },
+    /// A page of a PDF, which has no stored image and is rasterized instead.
+    PdfPage {
+        document: Arc<PathBuf>,
+        index: usize,
+    },
+}
+
+impl PageSource {
+    /// Reads just the first `length` bytes, for sniffing the file type.
+    fn read_head(&self, length: u64) -> io::Result<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(length as usize);
+
+        match self {
+            PageSource::ZipEntry { archive, index } => {
+                lock_archive(archive)?
+                    .by_index(*index)?
+                    .take(length)
+                    .read_to_end(&mut buffer)?;
+            }
+            PageSource::TarEntry {
+                archive,
+                offset,
+                size,
+                ..
+            } => {
+                open_at(archive, *offset)?
+                    .take(length.min(*size))
+                    .read_to_end(&mut buffer)?;
+            }
+            PageSource::File { path, .. } => {
+                File::open(path)?.take(length).read_to_end(&mut buffer)?;
+            }
+            PageSource::PdfPage { .. } => return Err(not_stored()),
+        }
+
+        Ok(buffer)
+    }
+
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        match self {
+            PageSource::ZipEntry { archive, index } => {
+                let mut archive = lock_archive(archive)?;
+                let mut entry = archive.by_index(*index)?;
+
+                let mut buffer = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut buffer)?;
+
+                Ok(buffer)
+            }
+            PageSource::TarEntry {
+                archive,
+                offset,
+                size,
+                ..
+            } => {
+                let mut buffer = Vec::with_capacity(*size as usize);
+                open_at(archive, *offset)?
+                    .take(*size)
+                    .read_to_end(&mut buffer)?;
+
+                Ok(buffer)
+            }
+            PageSource::File { path, .. } => std::fs::read(path),
+            PageSource::PdfPage { .. } => Err(not_stored()),
+        }
+    }
+}
+
+fn not_stored() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "PDF pages are rendered, not stored",
+    )
+}
+
+fn open_at(path: &Path, offset: u64) -> io::Result<File> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+
+    Ok(file)
+}
+
+/// The box, in pixels, that a page drawn at whatever resolution is asked of
+/// it should fit inside. Either side may be `u32::MAX` to leave it unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderSize {
+    pub width: u32,
+    pub height: u32,
 }
 
-#[derive(Debug, Clone, Eq)]
+/// How the tar file behind a comic is compressed as a whole.
+#[derive(Debug, Clone, Copy)]
+enum TarCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+#[derive(Debug, Clone)]
 pub struct Page {
     file_name: String,
-    path: PathBuf,
+    source: PageSource,
+    /// Position of the page in the archive's own listing.
+    archive_index: usize,
+    modified: Option<SystemTime>,
+    /// The comic's metadata says this page is a two-page spread by itself.
+    is_double_page: bool,
 }
 
 impl Page {
-    pub fn as_image(&self) -> Result<DynamicImage, ImageError> {
-        ImageReader::open(&self.path)?.decode()
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn is_double_page(&self) -> bool {
+        self.is_double_page
+    }
+
+    /// The page exactly as it is stored in the comic, still encoded.
+    pub fn as_bytes(&self) -> Result<Vec<u8>, ComicError> {
+        Ok(self.source.read()?)
     }
 
-    pub fn as_bytes(&self) -> Result<Vec<u8>, ImageError> {
-        let mut buffer: Vec<u8> = Vec::new();
+    /// Whether the page looks sharper when drawn at a higher resolution,
+    /// rather than being an image with a size of its own.
+    pub fn is_scalable(&self) -> bool {
+        matches!(self.source, PageSource::PdfPage { .. })
+    }
 
-        let _ = &self
-            .as_image()
-            .unwrap()
-            .write_to(&mut buffer, ImageOutputFormat::Png)
-            .unwrap();
+    /// Decodes the page. Scalable pages are drawn to fit `render_size`, images
+    /// come out at their own size.
+    pub fn as_image(&self, render_size: RenderSize) -> Result<DynamicImage, ComicError> {
+        if let PageSource::PdfPage { document, index } = &self.source {
+            return pdf::render_page(document, *index, render_size)
+                .map_err(|error| self.undecodable(error));
+        }
 
-        Ok(buffer)
+        let bytes = self.as_bytes()?;
+
+        image::load_from_memory(&bytes).map_err(|error| self.undecodable(error))
+    }
+
+    fn undecodable(&self, reason: impl ToString) -> ComicError {
+        ComicError::UndecodablePage {
+            file_name: self.file_name.clone(),
+            reason: reason.to_string(),
+        }
     }
 }
 
 impl Ord for Page {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.file_name.cmp(&other.file_name)
+        natural_sort::compare_paths(&self.file_name, &other.file_name)
     }
 }
 
@@ -55,22 +328,731 @@ impl PartialEq for Page {
     }
 }
 
+impl Eq for Page {}
+
+/// How the pages of a [`Comic`] are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageOrder {
+    /// By file name, numbers compared by value and folders kept together.
+    Natural,
+    /// The order the archive stores its entries in, or the reading order
+    /// it declares for books that have one.
+    Archive,
+    /// Oldest first, for releases whose names carry no order at all.
+    ModificationTime,
+}
+
+impl Default for PageOrder {
+    fn default() -> Self {
+        PageOrder::Natural
+    }
+}
+
+impl PageOrder {
+    pub fn next(self) -> Self {
+        match self {
+            PageOrder::Natural => PageOrder::Archive,
+            PageOrder::Archive => PageOrder::ModificationTime,
+            PageOrder::ModificationTime => PageOrder::Natural,
+        }
+    }
+
+    /// How the order is named where the reader sees it.
+    pub fn label(self) -> &'static str {
+        match self {
+            PageOrder::Natural => "Sorted by name",
+            PageOrder::Archive => "Archive order",
+            PageOrder::ModificationTime => "Sorted by date",
+        }
+    }
+
+    fn sort(self, pages: &mut [Page]) {
+        match self {
+            PageOrder::Natural => pages.sort(),
+            PageOrder::Archive => pages.sort_by_key(|page| page.archive_index),
+            PageOrder::ModificationTime => pages.sort_by(|a, b| {
+                // Pages without a timestamp go last.
+                match (a.modified, b.modified) {
+                    (Some(a_modified), Some(b_modified)) => a_modified.cmp(&b_modified),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+                .then_with(|| a.cmp(b))
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraKind {
+    /// Machine readable information about the comic, e.g. `ComicInfo.xml`.
+    Metadata,
+    /// Release notes and other human readable text.
+    Text,
+    Other,
+}
+
+/// A file in the comic that is not a page.
+#[derive(Debug, Clone)]
+pub struct Extra {
+    pub file_name: String,
+    pub kind: ExtraKind,
+    source: PageSource,
+}
+
+impl Extra {
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        self.source.read()
+    }
+}
+
+/// Lists the files under `directory` as if it were an archive, with names
+/// relative to the comic's folder given by `prefix`.
+///
+/// Hidden files and folders are skipped, as are symlinked folders so that a
+/// link back up the tree can't send this round in circles.
+fn collect_directory_entries(
+    directory: &Path,
+    prefix: &str,
+    entries: &mut Vec<ArchiveEntry>,
+) -> Result<(), ComicError> {
+    for dir_entry in fs::read_dir(directory)? {
+        let dir_entry = dir_entry?;
+        let name = dir_entry.file_name().to_string_lossy().into_owned();
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let file_name = format!("{}{}", prefix, name);
+        let path = dir_entry.path();
+        let file_type = dir_entry.file_type()?;
+
+        if file_type.is_dir() {
+            collect_directory_entries(&path, &format!("{}/", file_name), entries)?;
+            continue;
+        }
+
+        // Follows symlinks, a broken one is left out rather than failing the comic.
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => continue,
+        };
+
+        entries.push(ArchiveEntry {
+            file_name,
+            source: PageSource::File {
+                path,
+                temp_directory: None,
+            },
+            modified: metadata.modified().ok(),
+        });
+    }
+
+    Ok(())
+}
+
+/// The path of an archive entry relative to where it is extracted, or `None`
+/// if the name would climb out of that directory.
+fn safe_relative_path(entry_name: &str) -> Option<PathBuf> {
+    let relative_path: PathBuf = entry_name.split(|c| c == '/' || c == '\\').collect();
+
+    let is_safe = relative_path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if is_safe && relative_path.components().next().is_some() {
+        Some(relative_path)
+    } else {
+        None
+    }
+}
+
+/// Lists a zip archive. Only the central directory is read here, page data
+/// is decompressed when the page is displayed.
+fn zip_entries(archive: &SharedZipArchive) -> Result<Vec<ArchiveEntry>, ComicError> {
+    let mut zip_archive = lock_archive(archive)?;
+    let mut entries = Vec::with_capacity(zip_archive.len());
+
+    for index in 0..zip_archive.len() {
+        // Opening the entry without reading it is enough to find out
+        // whether it needs a password.
+        let listed = match zip_archive.by_index(index) {
+            Ok(entry) => Ok(list_zip_entry(&entry)),
+            Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)) => {
+                return Err(ComicError::EncryptedArchive)
+            }
+            Err(error) => Err(error),
+        };
+
+        // Anything else wrong with one entry, like a compression method we
+        // can't decode, only costs that entry. It is still listed from its
+        // header and ends up among the extras once its contents can't be
+        // read.
+        let listed = listed.or_else(|error| {
+            eprintln!("comic: can't decode zip entry {}: {}", index, error);
+            zip_archive
+                .by_index_raw(index)
+                .map(|entry| list_zip_entry(&entry))
+        });
+
+        match listed {
+            Ok((file_name, modified)) => entries.push(ArchiveEntry {
+                file_name,
+                source: PageSource::ZipEntry {
+                    archive: archive.clone(),
+                    index,
+                },
+                modified: Some(modified),
+            }),
+            Err(error) => eprintln!("comic: skipping zip entry {}: {}", index, error),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The name and modification time of a zip entry.
+fn list_zip_entry(entry: &ZipFile<'_>) -> (String, SystemTime) {
+    // Zip timestamps carry no time zone, which is fine for ordering.
+    let seconds = entry.last_modified().to_time().to_timespec().sec;
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64);
+
+    (entry.name().to_string(), modified)
+}
+
+/// Parses the comic's `ComicInfo.xml`. Broken metadata is only reported,
+/// the comic reads fine without it.
+fn read_comic_info(extras: &[Extra]) -> Option<ComicMetadata> {
+    let extra = extras
+        .iter()
+        .find(|extra| extra.kind == ExtraKind::Metadata && is_comic_info(&extra.file_name))?;
+
+    let parsed = extra
+        .read()
+        .map_err(|error| error.to_string())
+        .and_then(|document| ComicMetadata::parse(&document));
+
+    match parsed {
+        Ok(metadata) => Some(metadata),
+        Err(error) => {
+            eprintln!(
+                "comic: skipping unreadable {:?}: {}",
+                extra.file_name, error
+            );
+
+            None
+        }
+    }
+}
+
+/// Works out what kind of archive the file at `path` is, going by its
+/// contents first and its name second.
+fn detect_format(path: &Path) -> Result<ArchiveFormat, ComicError> {
+    let claimed = ArchiveFormat::from_extension(path);
+    let sniffed = ArchiveFormat::sniff_file(path)?;
+
+    ArchiveFormat::resolve(claimed, sniffed).ok_or(ComicError::InvalidArchiveType)
+}
+
+/// Whether an entry is an image that could be the comic's cover, going by
+/// its name alone.
+fn is_cover_candidate(file_name: &str) -> bool {
+    let base_name = base_name(file_name);
+    let extension = base_name.rsplit_once('.').map(|(_, extension)| extension);
+
+    !is_clutter(file_name)
+        && extension.map_or(false, |extension| IMAGE_EXTENSIONS.contains(&extension))
+}
+
+/// Extracts the first image of a RAR archive in natural order, along with
+/// everything stored before it, since RAR can only be read front to back.
+fn first_rar_image(path: &Path) -> Result<(String, Vec<u8>), ComicError> {
+    let path_string = path
+        .to_str()
+        .ok_or_else(|| ComicError::Io(format!("{:?} is not a valid UTF-8 path", path)))?
+        .to_string();
+
+    let cover_name = RarArchive::new(path_string.clone())
+        .list()?
+        .process()?
+        .into_iter()
+        .filter(|entry| !entry.is_directory() && is_cover_candidate(&entry.filename))
+        .map(|entry| entry.filename)
+        .min_by(|a, b| natural_sort::compare_paths(a, b))
+        .ok_or(ComicError::EmptyComic)?;
+
+    let temp_directory = Comic::create_temp_directory()?;
+    let destination = temp_directory
+        .path()
+        .to_str()
+        .unwrap_or_default()
+        .to_string();
+
+    for entry in RarArchive::new(path_string).extract_to(destination)? {
+        if entry?.filename == cover_name {
+            break;
+        }
+    }
+
+    let bytes = fs::read(temp_directory.path().join(&cover_name))?;
+
+    Ok((cover_name, bytes))
+}
+
+/// Finds the first image of a 7z archive in natural order in a single pass,
+/// keeping only the best candidate so far in memory.
+fn first_7z_image(path: &Path) -> Result<(String, Vec<u8>), ComicError> {
+    let mut archive = SevenZReader::open(path, Password::empty())?;
+    let mut cover: Option<(String, Vec<u8>)> = None;
+
+    archive.for_each_entries(|entry, data| {
+        let is_earlier = !entry.is_directory()
+            && entry.has_stream()
+            && is_cover_candidate(entry.name())
+            && cover.as_ref().map_or(true, |(cover_name, _)| {
+                natural_sort::compare_paths(entry.name(), cover_name) == Ordering::Less
+            });
+
+        if is_earlier {
+            let mut bytes = Vec::new();
+            data.read_to_end(&mut bytes)?;
+
+            cover = Some((entry.name().to_string(), bytes));
+        } else {
+            io::copy(data, &mut io::sink())?;
+        }
+
+        Ok(true)
+    })?;
+
+    cover.ok_or(ComicError::EmptyComic)
+}
+
+/// Lists the files in a RAR archive, extracting only as far as its
+/// `ComicInfo.xml` if it has one.
+fn list_rar(path: &Path) -> Result<ArchiveListing, ComicError> {
+    let path_string = path
+        .to_str()
+        .ok_or_else(|| ComicError::Io(format!("{:?} is not a valid UTF-8 path", path)))?
+        .to_string();
+
+    let file_names: Vec<String> = RarArchive::new(path_string.clone())
+        .list()?
+        .process()?
+        .into_iter()
+        .filter(|entry| !entry.is_directory())
+        .map(|entry| entry.filename)
+        .collect();
+
+    let comic_info_name = match file_names.iter().find(|file_name| is_comic_info(file_name)) {
+        Some(file_name) => file_name.clone(),
+        None => {
+            return Ok(ArchiveListing {
+                file_names,
+                comic_info: None,
+            })
+        }
+    };
+
+    let temp_directory = Comic::create_temp_directory()?;
+    let destination = temp_directory
+        .path()
+        .to_str()
+        .unwrap_or_default()
+        .to_string();
+
+    for entry in RarArchive::new(path_string).extract_to(destination)? {
+        if entry?.filename == comic_info_name {
+            break;
+        }
+    }
+
+    Ok(ArchiveListing {
+        file_names,
+        comic_info: fs::read(temp_directory.path().join(&comic_info_name)).ok(),
+    })
+}
+
+/// Lists the files in a 7z archive in one pass. Everything is decompressed
+/// on the way, but only `ComicInfo.xml` is kept.
+fn list_7z(path: &Path) -> Result<ArchiveListing, ComicError> {
+    let mut archive = SevenZReader::open(path, Password::empty())?;
+    let mut file_names = Vec::new();
+    let mut comic_info = None;
+
+    archive.for_each_entries(|entry, data| {
+        if entry.is_directory() || !entry.has_stream() {
+            return Ok(true);
+        }
+
+        if is_comic_info(entry.name()) {
+            let mut bytes = Vec::new();
+            data.read_to_end(&mut bytes)?;
+
+            comic_info = Some(bytes);
+        } else {
+            io::copy(data, &mut io::sink())?;
+        }
+
+        file_names.push(entry.name().to_string());
+
+        Ok(true)
+    })?;
+
+    Ok(ArchiveListing {
+        file_names,
+        comic_info,
+    })
+}
+
+/// Lists the files in a compressed tarball by streaming through it, without
+/// the temp file that opening it needs.
+fn list_compressed_tar(
+    path: &Path,
+    compression: TarCompression,
+) -> Result<ArchiveListing, ComicError> {
+    let mut file_names = Vec::new();
+    let mut comic_info = None;
+
+    for entry in tar::Archive::new(tar_stream(path, compression)?).entries()? {
+        let mut entry = entry?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let file_name = entry.path()?.to_string_lossy().into_owned();
+
+        if is_comic_info(&file_name) {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+
+            comic_info = Some(bytes);
+        }
+
+        file_names.push(file_name);
+    }
+
+    Ok(ArchiveListing {
+        file_names,
+        comic_info,
+    })
+}
+
+/// The names of the files in an archive, and its `ComicInfo.xml` if it has
+/// one, which is all a summary of it needs.
+struct ArchiveListing {
+    file_names: Vec<String>,
+    comic_info: Option<Vec<u8>>,
+}
+
+impl ArchiveListing {
+    /// Counts the pages by name, since telling whether each image really
+    /// decodes would mean extracting it.
+    fn summarize(self, title: String) -> ComicSummary {
+        let metadata = self
+            .comic_info
+            .and_then(|document| match ComicMetadata::parse(&document) {
+                Ok(metadata) => Some(metadata),
+                Err(error) => {
+                    eprintln!("comic: skipping unreadable ComicInfo.xml: {}", error);
+
+                    None
+                }
+            });
+
+        let image_count = self
+            .file_names
+            .iter()
+            .filter(|file_name| is_cover_candidate(file_name))
+            .count();
+
+        let deleted_count = metadata.as_ref().map_or(0, |metadata| {
+            (0..image_count)
+                .filter(|image| {
+                    metadata
+                        .page(*image)
+                        .map_or(false, |info| info.page_type == PageType::Deleted)
+                })
+                .count()
+        });
+
+        ComicSummary {
+            title: metadata
+                .as_ref()
+                .and_then(ComicMetadata::display_title)
+                .unwrap_or(title),
+            series: metadata.and_then(|metadata| metadata.series),
+            page_count: image_count - deleted_count,
+        }
+    }
+}
+
+/// Whether an entry is the comic's `ComicInfo.xml`, wherever it is stored.
+fn is_comic_info(file_name: &str) -> bool {
+    base_name(file_name) == COMIC_INFO_FILE_NAME
+}
+
+/// Finds the first image of a tar file in natural order in a single pass
+/// through it, decompressing on the way rather than into a temp file.
+fn first_tar_image(
+    path: &Path,
+    compression: TarCompression,
+) -> Result<(String, Vec<u8>), ComicError> {
+    let mut cover: Option<(String, Vec<u8>)> = None;
+
+    for entry in tar::Archive::new(tar_stream(path, compression)?).entries()? {
+        let mut entry = entry?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let file_name = entry.path()?.to_string_lossy().into_owned();
+
+        let is_earlier = is_cover_candidate(&file_name)
+            && cover.as_ref().map_or(true, |(cover_name, _)| {
+                natural_sort::compare_paths(&file_name, cover_name) == Ordering::Less
+            });
+
+        if is_earlier {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+
+            cover = Some((file_name, bytes));
+        }
+    }
+
+    cover.ok_or(ComicError::EmptyComic)
+}
+
+/// The contents of a tar file as they are read, decompressed if need be.
+fn tar_stream(path: &Path, compression: TarCompression) -> Result<Box<dyn Read>, ComicError> {
+    let file = BufReader::new(File::open(path)?);
+
+    Ok(match compression {
+        TarCompression::None => Box::new(file),
+        TarCompression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        TarCompression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+    })
+}
+
+/// An entry of an archive before it is sorted into pages and extras.
+struct ArchiveEntry {
+    file_name: String,
+    source: PageSource,
+    modified: Option<SystemTime>,
+}
+
+enum EntryKind {
+    Page,
+    Extra(ExtraKind),
+    Ignored,
+}
+
+/// Whether an entry is a directory or something left behind by an archiver
+/// or file manager rather than part of the comic.
+fn is_clutter(file_name: &str) -> bool {
+    let is_directory = file_name.ends_with('/') || file_name.ends_with('\\');
+
+    let mut components = file_name.split(|c| c == '/' || c == '\\');
+    let is_resource_fork = components.any(|component| component == "__MACOSX");
+
+    let base_name = base_name(file_name);
+
+    is_directory
+        || is_resource_fork
+        || base_name.starts_with("._")
+        || IGNORED_FILE_NAMES.contains(&base_name.as_str())
+}
+
+/// The last component of an entry's name, lowercased.
+fn base_name(file_name: &str) -> String {
+    file_name
+        .rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn classify_entry(file_name: &str, source: &PageSource) -> EntryKind {
+    if is_clutter(file_name) {
+        return EntryKind::Ignored;
+    }
+
+    let base_name = base_name(file_name);
+
+    let extension = base_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .unwrap_or_default();
+
+    if METADATA_EXTENSIONS.contains(&extension) {
+        return EntryKind::Extra(ExtraKind::Metadata);
+    }
+
+    if TEXT_EXTENSIONS.contains(&extension) {
+        return EntryKind::Extra(ExtraKind::Text);
+    }
+
+    // The extension only tells us what the file claims to be, the magic bytes
+    // decide whether the image crate can actually decode it. Files without a
+    // known extension get the same check since some scanners drop them.
+    let is_decodable_image = source
+        .read_head(MAGIC_BYTES_LENGTH)
+        .map(|head| image::guess_format(&head).is_ok())
+        .unwrap_or(false);
+
+    if is_decodable_image {
+        return EntryKind::Page;
+    }
+
+    if IMAGE_EXTENSIONS.contains(&extension) {
+        eprintln!("comic: skipping undecodable image {:?}", file_name);
+    }
+
+    EntryKind::Extra(ExtraKind::Other)
+}
+
+/// What the library shows of a comic without opening it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComicSummary {
+    /// The title from the metadata, or the file name without one.
+    pub title: String,
+    pub series: Option<String>,
+    pub page_count: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct Comic {
     pub title: String,
-    pub folder_path: PathBuf,
+    /// The file the comic was opened from.
+    pub path: PathBuf,
     pub pages: Vec<Page>,
+    pub page_order: PageOrder,
+    /// Everything in the archive that isn't a page, such as metadata and notes.
+    pub extras: Vec<Extra>,
+    /// Whether the comic says it reads right to left, if it says anything.
+    pub right_to_left: Option<bool>,
+    /// What the comic's `ComicInfo.xml` says about it, if it has one.
+    pub metadata: Option<ComicMetadata>,
+    pub warning: Option<ComicWarning>,
 }
 
 impl Comic {
     pub async fn from_archive_path(path: PathBuf) -> Result<Self, ComicError> {
-        println!("got archive {:?}", path);
+        if path.is_dir() {
+            return Comic::from_directory(path);
+        }
+
+        let format = detect_format(&path)?;
+        let claimed = ArchiveFormat::from_extension(&path);
+
+        let mut comic = match format {
+            ArchiveFormat::Zip => Comic::from_zip(path),
+            ArchiveFormat::Rar => Comic::from_rar(path),
+            ArchiveFormat::SevenZip => Comic::from_7z(path),
+            ArchiveFormat::Tar => Comic::from_tar(path, TarCompression::None),
+            ArchiveFormat::Gzip => Comic::from_tar(path, TarCompression::Gzip),
+            ArchiveFormat::Zstd => Comic::from_tar(path, TarCompression::Zstd),
+            ArchiveFormat::Pdf => Comic::from_pdf(path),
+            ArchiveFormat::Epub => Comic::from_epub(path),
+        }?;
+
+        // Opened all the same, going by what the file really is.
+        comic.warning = claimed
+            .filter(|claimed| claimed.is_misnamed_as(format))
+            .map(|claimed| ComicWarning::Misnamed {
+                claimed,
+                actual: format,
+            });
+
+        Ok(comic)
+    }
+
+    /// Decodes the first page of the comic at `path`, for a cover. Scalable
+    /// pages are drawn to fit `render_size`.
+    ///
+    /// Archives that would otherwise be extracted whole are only read as far
+    /// as the first page, and tar files are streamed through once keeping
+    /// just that page. Zips, EPUBs, PDFs and folders open the usual way since
+    /// their pages are read on demand anyway.
+    pub async fn cover_image(
+        path: PathBuf,
+        render_size: RenderSize,
+    ) -> Result<DynamicImage, ComicError> {
+        let format = if path.is_dir() {
+            None
+        } else {
+            Some(detect_format(&path)?)
+        };
+
+        let (file_name, bytes) = match format {
+            Some(ArchiveFormat::Rar) => first_rar_image(&path)?,
+            Some(ArchiveFormat::SevenZip) => first_7z_image(&path)?,
+            Some(ArchiveFormat::Tar) => first_tar_image(&path, TarCompression::None)?,
+            Some(ArchiveFormat::Gzip) => first_tar_image(&path, TarCompression::Gzip)?,
+            Some(ArchiveFormat::Zstd) => first_tar_image(&path, TarCompression::Zstd)?,
+            _ => {
+                let comic = Comic::from_archive_path(path).await?;
+                let page = comic.pages.first().ok_or(ComicError::EmptyComic)?;
+
+                return page.as_image(render_size);
+            }
+        };
+
+        image::load_from_memory(&bytes).map_err(|error| ComicError::UndecodablePage {
+            file_name,
+            reason: error.to_string(),
+        })
+    }
+
+    /// Summarizes the comic at `path` for the library.
+    ///
+    /// Archives that opening would extract whole are only listed instead,
+    /// the rest are opened the usual way since that reads little of them.
+    pub async fn summarize(path: PathBuf) -> Result<ComicSummary, ComicError> {
+        let format = if path.is_dir() {
+            None
+        } else {
+            Some(detect_format(&path)?)
+        };
 
-        return match path.extension() {
-            Some(ext) if ext == "zip" || ext == "cbz" => Comic::from_zip(path),
-            Some(ext) if ext == "rar" || ext == "cbr" => Comic::from_rar(path),
-            _ => Err(ComicError::InvalidArchiveType),
+        let listing = match format {
+            Some(ArchiveFormat::Rar) => list_rar(&path)?,
+            Some(ArchiveFormat::SevenZip) => list_7z(&path)?,
+            Some(ArchiveFormat::Gzip) => list_compressed_tar(&path, TarCompression::Gzip)?,
+            Some(ArchiveFormat::Zstd) => list_compressed_tar(&path, TarCompression::Zstd)?,
+            _ => {
+                let comic = Comic::from_archive_path(path).await?;
+
+                return Ok(ComicSummary {
+                    title: comic.display_title(),
+                    series: comic
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.series.clone()),
+                    page_count: comic.pages.len(),
+                });
+            }
         };
+
+        let title = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let summary = listing.summarize(title);
+
+        if summary.page_count == 0 {
+            return Err(ComicError::EmptyComic);
+        }
+
+        Ok(summary)
     }
 
     fn from_zip(path: PathBuf) -> Result<Self, ComicError> {
@@ -81,33 +1063,75 @@ impl Comic {
             .unwrap_or_default()
             .to_string();
 
-        let temp_directory = tempfile::tempdir().unwrap().into_path();
+        let archive = open_zip(&path)?;
+        let entries = zip_entries(&archive)?;
 
-        let file = File::open(&path).unwrap();
-        let reader = std::io::BufReader::new(file);
+        Comic::from_entries(file_name, path, entries)
+    }
 
-        let mut zip_archive = ZipArchive::new(reader).unwrap();
+    /// Reads a fixed-layout EPUB in the order its spine declares, with one
+    /// page per image rather than per file in the archive.
+    fn from_epub(path: PathBuf) -> Result<Self, ComicError> {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
 
-        let mut pages: Vec<Page> = zip_archive
-            .file_names()
-            .map(|name| {
-                let path = temp_directory.join(name);
+        let archive = open_zip(&path)?;
 
-                Page {
-                    file_name: name.to_string(),
-                    path,
-                }
-            })
-            .collect::<Vec<Page>>();
+        let spine = epub::read_spine(|name| {
+            let mut zip_archive = lock_archive(&archive)?;
+            let mut entry = zip_archive.by_name(name)?;
+
+            let mut buffer = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut buffer)?;
+
+            Ok(buffer)
+        })?;
+
+        let mut pages = Vec::new();
+        let mut extras = Vec::new();
+
+        // The markup, styles and fonts around the images are of no use to a
+        // comic reader, only the pages and the package document are kept.
+        for entry in zip_entries(&archive)? {
+            if let Some(archive_index) =
+                spine.pages.iter().position(|page| *page == entry.file_name)
+            {
+                pages.push(Page {
+                    file_name: entry.file_name,
+                    source: entry.source,
+                    archive_index,
+                    modified: entry.modified,
+                    is_double_page: false,
+                });
+            } else if entry.file_name == spine.package_path {
+                extras.push(Extra {
+                    file_name: entry.file_name,
+                    kind: ExtraKind::Metadata,
+                    source: entry.source,
+                });
+            }
+        }
 
-        pages.sort();
+        if pages.is_empty() {
+            return Err(ComicError::EmptyComic);
+        }
 
-        zip_archive.extract(&temp_directory).unwrap();
+        let page_order = PageOrder::Archive;
+        page_order.sort(&mut pages);
 
         Ok(Self {
             title: file_name,
-            folder_path: temp_directory,
+            path,
             pages,
+            page_order,
+            extras,
+            right_to_left: Some(spine.right_to_left),
+            metadata: None,
+            warning: None,
         })
     }
 
@@ -119,39 +1143,406 @@ impl Comic {
             .unwrap_or_default()
             .to_string();
 
-        let path_string = path.to_str().unwrap().to_string();
+        let path_string = path
+            .to_str()
+            .ok_or_else(|| ComicError::Io(format!("{:?} is not a valid UTF-8 path", path)))?
+            .to_string();
 
-        let temp_directory = tempfile::tempdir().unwrap().into_path();
+        let temp_directory = Arc::new(Comic::create_temp_directory()?);
 
-        let mut pages: Vec<Page> = RarArchive::new(path_string.clone())
-            .list()
-            .unwrap()
-            .process()
-            .unwrap()
+        // unrar can only walk the archive front to back, so there is no cheap
+        // way to read a single page and everything is extracted up front.
+        RarArchive::new(path_string.clone())
+            .extract_to(
+                temp_directory
+                    .path()
+                    .to_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            )?
+            .process()?;
+
+        let entries: Vec<ArchiveEntry> = RarArchive::new(path_string.clone())
+            .list()?
+            .process()?
             .into_iter()
+            .filter(|entry| !entry.is_directory())
             .map(|entry| {
                 let filename = entry.filename;
-                let path = temp_directory.join(&filename);
+                let path = temp_directory.path().join(&filename);
+                let modified = fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
 
-                Page {
+                ArchiveEntry {
                     file_name: filename,
-                    path,
+                    source: PageSource::File {
+                        path,
+                        temp_directory: Some(temp_directory.clone()),
+                    },
+                    modified,
                 }
             })
-            .collect::<Vec<Page>>();
+            .collect();
+
+        Comic::from_entries(file_name, path, entries)
+    }
 
-        pages.sort();
+    fn from_7z(path: PathBuf) -> Result<Self, ComicError> {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
 
-        RarArchive::new(path_string.clone())
-            .extract_to(temp_directory.to_str().unwrap_or_default().to_string())
-            .unwrap()
-            .process()
-            .unwrap();
+        let temp_directory = Arc::new(Comic::create_temp_directory()?);
+
+        let mut archive = SevenZReader::open(&path, Password::empty())?;
+
+        let mut entries = Vec::new();
+        let mut write_error = None;
+
+        // Solid archives compress many files as one stream, so a single page
+        // can't be decoded without decoding everything before it. Like RAR,
+        // the whole archive is extracted in one pass instead.
+        archive.for_each_entries(|entry, data| {
+            if entry.is_directory() || !entry.has_stream() {
+                return Ok(true);
+            }
+
+            let target = match safe_relative_path(entry.name()) {
+                Some(relative_path) => temp_directory.path().join(relative_path),
+                None => {
+                    eprintln!(
+                        "comic: skipping 7z entry outside the archive {:?}",
+                        entry.name()
+                    );
+                    io::copy(data, &mut io::sink())?;
+
+                    return Ok(true);
+                }
+            };
+
+            let written = target
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| File::create(&target))
+                .and_then(|mut file| io::copy(data, &mut file));
+
+            match written {
+                Ok(_) => {
+                    entries.push(ArchiveEntry {
+                        file_name: entry.name().to_string(),
+                        source: PageSource::File {
+                            path: target,
+                            temp_directory: Some(temp_directory.clone()),
+                        },
+                        modified: entry
+                            .has_last_modified_date
+                            .then(|| entry.last_modified_date().into()),
+                    });
+
+                    Ok(true)
+                }
+                Err(error) => {
+                    write_error = Some(error);
+
+                    Ok(false)
+                }
+            }
+        })?;
+
+        if let Some(error) = write_error {
+            return Err(error.into());
+        }
+
+        Comic::from_entries(file_name, path, entries)
+    }
+
+    /// Lists a tar file, remembering where each entry's data starts so pages
+    /// can be read straight from there later.
+    ///
+    /// A compressed tarball can't be read from the middle, so it is
+    /// decompressed once into a temp file and that is indexed instead.
+    fn from_tar(path: PathBuf, compression: TarCompression) -> Result<Self, ComicError> {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let (tar_path, temp_directory) = match compression {
+            TarCompression::None => (path.clone(), None),
+            TarCompression::Gzip | TarCompression::Zstd => {
+                let temp_directory = Comic::create_temp_directory()?;
+                let tar_path = temp_directory.path().join("comic.tar");
+
+                let compressed = BufReader::new(File::open(&path)?);
+                let mut tar_file = File::create(&tar_path)?;
+
+                match compression {
+                    TarCompression::Gzip => {
+                        io::copy(
+                            &mut flate2::read::MultiGzDecoder::new(compressed),
+                            &mut tar_file,
+                        )?;
+                    }
+                    TarCompression::Zstd => {
+                        io::copy(
+                            &mut zstd::stream::read::Decoder::new(compressed)?,
+                            &mut tar_file,
+                        )?;
+                    }
+                    TarCompression::None => {}
+                }
+
+                (tar_path, Some(Arc::new(temp_directory)))
+            }
+        };
+
+        let archive = Arc::new(tar_path);
+        let mut tar_archive = tar::Archive::new(BufReader::new(File::open(archive.as_ref())?));
+
+        let mut entries = Vec::new();
+
+        for entry in tar_archive.entries()? {
+            let entry = entry?;
+
+            // Links and the like have no data of their own.
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let modified = entry
+                .header()
+                .mtime()
+                .ok()
+                .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+
+            entries.push(ArchiveEntry {
+                file_name: entry.path()?.to_string_lossy().into_owned(),
+                source: PageSource::TarEntry {
+                    archive: archive.clone(),
+                    offset: entry.raw_file_position(),
+                    size: entry.size(),
+                    temp_directory: temp_directory.clone(),
+                },
+                modified,
+            });
+        }
+
+        Comic::from_entries(file_name, path, entries)
+    }
+
+    /// Lists the pages of a PDF. Nothing is rendered until a page is shown.
+    fn from_pdf(path: PathBuf) -> Result<Self, ComicError> {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let page_count = pdf::page_count(&path)?;
+
+        if page_count == 0 {
+            return Err(ComicError::EmptyComic);
+        }
+
+        let document = Arc::new(path.clone());
+        let modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        // Numbered names sort naturally into the order of the document.
+        let pages = (0..page_count)
+            .map(|index| Page {
+                file_name: format!("Page {}", index + 1),
+                source: PageSource::PdfPage {
+                    document: document.clone(),
+                    index,
+                },
+                archive_index: index,
+                modified,
+                is_double_page: false,
+            })
+            .collect();
 
         Ok(Self {
             title: file_name,
-            folder_path: temp_directory,
+            path,
             pages,
+            page_order: PageOrder::default(),
+            extras: Vec::new(),
+            right_to_left: None,
+            metadata: None,
+            warning: None,
         })
     }
+
+    /// Reads a folder of images in place, including any chapter folders in it.
+    pub fn from_directory(path: PathBuf) -> Result<Self, ComicError> {
+        let title = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let mut entries = Vec::new();
+        collect_directory_entries(&path, "", &mut entries)?;
+
+        // There is no archive order to speak of, directory listings come back
+        // in whatever order the filesystem keeps them.
+        entries.sort_by(|a, b| natural_sort::compare_paths(&a.file_name, &b.file_name));
+
+        Comic::from_entries(title, path, entries)
+    }
+
+    /// Sorts the entries of an archive into pages and extras, dropping
+    /// directories and filesystem junk.
+    fn from_entries(
+        title: String,
+        path: PathBuf,
+        entries: Vec<ArchiveEntry>,
+    ) -> Result<Self, ComicError> {
+        let mut pages = Vec::new();
+        let mut extras = Vec::new();
+
+        for (archive_index, entry) in entries.into_iter().enumerate() {
+            match classify_entry(&entry.file_name, &entry.source) {
+                EntryKind::Page => pages.push(Page {
+                    file_name: entry.file_name,
+                    source: entry.source,
+                    archive_index,
+                    modified: entry.modified,
+                    is_double_page: false,
+                }),
+                EntryKind::Extra(kind) => extras.push(Extra {
+                    file_name: entry.file_name,
+                    kind,
+                    source: entry.source,
+                }),
+                EntryKind::Ignored => {}
+            }
+        }
+
+        let page_order = PageOrder::default();
+        page_order.sort(&mut pages);
+
+        let metadata = read_comic_info(&extras);
+
+        // Page numbers in the metadata count the images in natural order,
+        // which is the order the pages are in at this point.
+        if let Some(metadata) = &metadata {
+            pages = pages
+                .into_iter()
+                .enumerate()
+                .filter_map(|(image, mut page)| match metadata.page(image) {
+                    Some(info) if info.page_type == PageType::Deleted => None,
+                    Some(info) => {
+                        page.is_double_page =
+                            info.double_page || info.page_type == PageType::DoubleSpread;
+
+                        Some(page)
+                    }
+                    None => Some(page),
+                })
+                .collect();
+        }
+
+        if pages.is_empty() {
+            return Err(ComicError::EmptyComic);
+        }
+
+        Ok(Self {
+            title,
+            path,
+            pages,
+            page_order,
+            extras,
+            right_to_left: metadata.as_ref().and_then(ComicMetadata::right_to_left),
+            metadata,
+            warning: None,
+        })
+    }
+
+    /// The title the metadata gives the comic, or its file name without one.
+    pub fn display_title(&self) -> String {
+        self.metadata
+            .as_ref()
+            .and_then(ComicMetadata::display_title)
+            .unwrap_or_else(|| self.title.clone())
+    }
+
+    pub fn set_page_order(&mut self, page_order: PageOrder) {
+        self.page_order = page_order;
+        page_order.sort(&mut self.pages);
+    }
+
+    /// Temp directories are named `comik-<pid>-<random>` so that a later run can
+    /// tell which ones were left behind by a process that no longer exists.
+    fn create_temp_directory() -> io::Result<TempDir> {
+        tempfile::Builder::new()
+            .prefix(&format!("{}{}-", TEMP_DIRECTORY_PREFIX, std::process::id()))
+            .tempdir()
+    }
+}
+
+/// Removes temp directories left behind by earlier runs that crashed or were
+/// killed before their comics were dropped.
+pub fn remove_stale_temp_directories() {
+    let entries = match fs::read_dir(std::env::temp_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name();
+        let pid = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(TEMP_DIRECTORY_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|pid| pid.parse::<u32>().ok());
+
+        let pid = match pid {
+            Some(pid) if pid != std::process::id() => pid,
+            _ => continue,
+        };
+
+        let is_directory = entry
+            .file_type()
+            .map(|file_type| file_type.is_dir())
+            .unwrap_or(false);
+
+        if is_directory && is_temp_directory_stale(pid, &entry) {
+            eprintln!("comic: removing stale temp directory {:?}", entry.path());
+
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_temp_directory_stale(pid: u32, _entry: &fs::DirEntry) -> bool {
+    !PathBuf::from("/proc").join(pid.to_string()).exists()
+}
+
+/// Without a cheap way to check whether the process is alive, a directory is
+/// only considered abandoned once it is a day old.
+#[cfg(not(target_os = "linux"))]
+fn is_temp_directory_stale(_pid: u32, entry: &fs::DirEntry) -> bool {
+    use std::time::{Duration, SystemTime};
+
+    const STALE_TEMP_DIRECTORY_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+    entry
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|age| age > STALE_TEMP_DIRECTORY_AGE)
+        .unwrap_or(false)
 }
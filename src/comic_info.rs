@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use xml::reader::{EventReader, XmlEvent};
+
+/// Name of the metadata file ComicRack and most taggers put in archives.
+pub const COMIC_INFO_FILE_NAME: &str = "comicinfo.xml";
+
+/// Whether a comic is manga, as `ComicInfo.xml` puts it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Manga {
+    Unknown,
+    No,
+    /// Drawn as manga, but the pages were flipped to read left to right.
+    Yes,
+    YesAndRightToLeft,
+}
+
+impl Default for Manga {
+    fn default() -> Self {
+        Manga::Unknown
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageType {
+    FrontCover,
+    InnerCover,
+    Roundup,
+    Story,
+    Advertisement,
+    Editorial,
+    Letters,
+    Preview,
+    BackCover,
+    /// Not part of the ComicInfo schema, but written by some taggers instead
+    /// of setting `DoublePage`.
+    DoubleSpread,
+    Other,
+    /// A page kept in the archive that isn't meant to be shown, such as a
+    /// scanner's credits.
+    Deleted,
+}
+
+impl PageType {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "FrontCover" => PageType::FrontCover,
+            "InnerCover" => PageType::InnerCover,
+            "Roundup" => PageType::Roundup,
+            "Story" => PageType::Story,
+            "Advertisement" => PageType::Advertisement,
+            "Editorial" => PageType::Editorial,
+            "Letters" => PageType::Letters,
+            "Preview" => PageType::Preview,
+            "BackCover" => PageType::BackCover,
+            "DoubleSpread" => PageType::DoubleSpread,
+            "Deleted" => PageType::Deleted,
+            _ => PageType::Other,
+        }
+    }
+}
+
+/// What `ComicInfo.xml` says about one page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageInfo {
+    /// The page's position among the comic's images in natural order,
+    /// counted from zero.
+    pub image: usize,
+    pub page_type: PageType,
+    /// The page is a two-page spread by itself.
+    pub double_page: bool,
+}
+
+/// The parts of `ComicInfo.xml` the reader has a use for.
+#[derive(Debug, Clone, Default)]
+pub struct ComicMetadata {
+    pub title: Option<String>,
+    pub series: Option<String>,
+    pub number: Option<String>,
+    pub volume: Option<String>,
+    pub year: Option<i32>,
+    pub writer: Option<String>,
+    pub penciller: Option<String>,
+    pub publisher: Option<String>,
+    pub summary: Option<String>,
+    pub manga: Manga,
+    pub pages: Vec<PageInfo>,
+}
+
+impl ComicMetadata {
+    pub fn parse(document: &[u8]) -> Result<Self, String> {
+        let mut fields: HashMap<String, String> = HashMap::new();
+        let mut pages = Vec::new();
+        let mut depth = 0;
+        let mut field = None;
+
+        for event in EventReader::new(document) {
+            match event.map_err(|error| error.to_string())? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    depth += 1;
+
+                    if name.local_name == "Page" {
+                        let attribute = |wanted: &str| {
+                            attributes
+                                .iter()
+                                .find(|attribute| attribute.name.local_name == wanted)
+                                .map(|attribute| attribute.value.trim())
+                        };
+
+                        if let Some(image) = attribute("Image").and_then(|image| image.parse().ok())
+                        {
+                            pages.push(PageInfo {
+                                image,
+                                page_type: attribute("Type")
+                                    .map_or(PageType::Story, PageType::from_name),
+                                double_page: attribute("DoublePage")
+                                    .map_or(false, |value| value.eq_ignore_ascii_case("true")),
+                            });
+                        }
+                    }
+
+                    // Only the fields directly under `<ComicInfo>` are wanted.
+                    field = Some(name.local_name).filter(|_| depth == 2);
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(field) = &field {
+                        fields.entry(field.clone()).or_default().push_str(&text);
+                    }
+                }
+                XmlEvent::EndElement { .. } => {
+                    depth -= 1;
+                    field = None;
+                }
+                _ => {}
+            }
+        }
+
+        let mut take = |name: &str| {
+            fields
+                .remove(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Ok(Self {
+            title: take("Title"),
+            series: take("Series"),
+            number: take("Number"),
+            volume: take("Volume"),
+            // Unknown years are written as -1 by ComicRack.
+            year: take("Year")
+                .and_then(|year| year.parse().ok())
+                .filter(|year| *year > 0),
+            writer: take("Writer"),
+            penciller: take("Penciller"),
+            publisher: take("Publisher"),
+            summary: take("Summary"),
+            manga: match take("Manga").as_deref() {
+                Some("No") => Manga::No,
+                Some("Yes") => Manga::Yes,
+                Some("YesAndRightToLeft") => Manga::YesAndRightToLeft,
+                _ => Manga::Unknown,
+            },
+            pages,
+        })
+    }
+
+    /// A title to show for the comic, such as `Saga #12 (2013)`, or `None`
+    /// when the metadata names neither the series nor the issue.
+    pub fn display_title(&self) -> Option<String> {
+        let mut title = match (&self.series, &self.number, &self.title) {
+            (Some(series), Some(number), _) => format!("{} #{}", series, number),
+            (Some(series), None, Some(title)) => format!("{}: {}", series, title),
+            (Some(series), None, None) => series.clone(),
+            (None, _, Some(title)) => title.clone(),
+            (None, _, None) => return None,
+        };
+
+        if let Some(year) = self.year {
+            title.push_str(&format!(" ({})", year));
+        }
+
+        Some(title)
+    }
+
+    /// The reading direction the metadata asks for, if it asks for one.
+    pub fn right_to_left(&self) -> Option<bool> {
+        match self.manga {
+            Manga::Unknown => None,
+            Manga::No | Manga::Yes => Some(false),
+            Manga::YesAndRightToLeft => Some(true),
+        }
+    }
+
+    /// Labelled fields for the info panel, leaving out the ones not set.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let manga = match self.manga {
+            Manga::Unknown => None,
+            Manga::No => Some("No"),
+            Manga::Yes => Some("Yes"),
+            Manga::YesAndRightToLeft => Some("Yes, right to left"),
+        };
+
+        vec![
+            ("Title", self.title.clone()),
+            ("Series", self.series.clone()),
+            ("Number", self.number.clone()),
+            ("Volume", self.volume.clone()),
+            ("Year", self.year.map(|year| year.to_string())),
+            ("Writer", self.writer.clone()),
+            ("Penciller", self.penciller.clone()),
+            ("Publisher", self.publisher.clone()),
+            ("Manga", manga.map(str::to_string)),
+            ("Summary", self.summary.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| Some((label, value?)))
+        .collect()
+    }
+
+    pub fn page(&self, image: usize) -> Option<&PageInfo> {
+        self.pages.iter().find(|page| page.image == image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(document: &str) -> ComicMetadata {
+        ComicMetadata::parse(document.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn reads_the_fields_and_pages() {
+        let metadata = parse(
+            r#"<?xml version="1.0"?>
+<ComicInfo xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <Title> The Way Home </Title>
+  <Series>Saga</Series>
+  <Number>12</Number>
+  <Year>2013</Year>
+  <Manga>YesAndRightToLeft</Manga>
+  <Pages>
+    <Page Image="0" Type="FrontCover" />
+    <Page Image="1" />
+    <Page Image="2" Type="Deleted" />
+    <Page Image="3" DoublePage="True" />
+    <Page Image="4" Type="DoubleSpread" DoublePage="false" />
+    <Page Type="Story" />
+  </Pages>
+</ComicInfo>"#,
+        );
+
+        assert_eq!(metadata.title.as_deref(), Some("The Way Home"));
+        assert_eq!(metadata.year, Some(2013));
+        assert_eq!(metadata.right_to_left(), Some(true));
+        assert_eq!(metadata.display_title().as_deref(), Some("Saga #12 (2013)"));
+        assert_eq!(metadata.pages.len(), 5);
+
+        let page = |image| metadata.page(image).unwrap();
+
+        assert_eq!(page(0).page_type, PageType::FrontCover);
+        assert_eq!(page(1).page_type, PageType::Story);
+        assert_eq!(page(2).page_type, PageType::Deleted);
+        assert!(!page(2).double_page);
+        assert!(page(3).double_page);
+        assert_eq!(page(4).page_type, PageType::DoubleSpread);
+        assert!(!page(4).double_page);
+        assert!(metadata.page(5).is_none());
+    }
+
+    #[test]
+    fn an_unknown_year_is_left_out() {
+        let metadata = parse("<ComicInfo><Series>Saga</Series><Year>-1</Year></ComicInfo>");
+
+        assert_eq!(metadata.year, None);
+        assert_eq!(metadata.display_title().as_deref(), Some("Saga"));
+        assert_eq!(metadata.fields(), vec![("Series", "Saga".to_string())]);
+    }
+
+    #[test]
+    fn nested_fields_are_ignored() {
+        let metadata = parse(
+            "<ComicInfo>\
+               <Series>Saga</Series>\
+               <Extra><Title>Not the title</Title><Series>Nor the series</Series></Extra>\
+             </ComicInfo>",
+        );
+
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.series.as_deref(), Some("Saga"));
+    }
+
+    #[test]
+    fn empty_fields_are_left_out() {
+        let metadata = parse("<ComicInfo><Title>  </Title><Manga>Maybe</Manga></ComicInfo>");
+
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.manga, Manga::Unknown);
+        assert_eq!(metadata.display_title(), None);
+        assert!(metadata.fields().is_empty());
+    }
+
+    #[test]
+    fn broken_xml_is_an_error() {
+        assert!(ComicMetadata::parse(b"<ComicInfo><Title>Saga</Series></ComicInfo>").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::image_viewer::FitMode;
+
+const COMIC_SETTINGS_FILE_NAME: &str = "comics.toml";
+
+/// How the reader last had a comic set up, remembered between runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComicSettings {
+    pub fit_mode: Option<FitMode>,
+}
+
+/// Settings for every comic that has been opened, keyed by its path.
+///
+/// This is a convenience, so a missing or broken file just means starting
+/// from the defaults and never stops a comic from opening.
+#[derive(Debug, Clone, Default)]
+pub struct ComicSettingsStore {
+    comics: toml::value::Table,
+}
+
+impl ComicSettingsStore {
+    pub fn data_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("comik").join(COMIC_SETTINGS_FILE_NAME))
+    }
+
+    pub fn load() -> Self {
+        let comics = ComicSettingsStore::data_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| match contents.parse::<toml::Value>() {
+                Ok(toml::Value::Table(table)) => Some(table),
+                Ok(_) => None,
+                Err(error) => {
+                    eprintln!("comic settings: {}", error);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self { comics }
+    }
+
+    pub fn get(&self, comic_path: &Path) -> ComicSettings {
+        let table = match self
+            .comics
+            .get(&key(comic_path))
+            .and_then(toml::Value::as_table)
+        {
+            Some(table) => table,
+            None => return ComicSettings::default(),
+        };
+
+        ComicSettings {
+            fit_mode: table
+                .get("fit_mode")
+                .and_then(toml::Value::as_str)
+                .and_then(FitMode::from_name),
+        }
+    }
+
+    /// Remembers the settings for a comic and writes the whole store out.
+    pub fn set(&mut self, comic_path: &Path, settings: ComicSettings) {
+        let mut table = toml::value::Table::new();
+
+        if let Some(fit_mode) = settings.fit_mode {
+            table.insert(
+                "fit_mode".to_string(),
+                toml::Value::String(fit_mode.name().to_string()),
+            );
+        }
+
+        self.comics
+            .insert(key(comic_path), toml::Value::Table(table));
+
+        if let Err(error) = self.save() {
+            eprintln!("comic settings: could not save: {}", error);
+        }
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let path = match ComicSettingsStore::data_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&path, toml::to_string(&self.comics)?)?;
+
+        Ok(())
+    }
+}
+
+fn key(comic_path: &Path) -> String {
+    comic_path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::comic::ComicError;
+
+const CONTAINER_PATH: &str = "META-INF/container.xml";
+
+/// What a fixed-layout EPUB says about its pages.
+#[derive(Debug, Clone)]
+pub struct Spine {
+    /// Paths inside the archive of the image on each page, in reading order.
+    pub pages: Vec<String>,
+    /// Where the OPF package document is, for anyone wanting the metadata.
+    pub package_path: String,
+    /// Set by `page-progression-direction="rtl"`, as manga exports do.
+    pub right_to_left: bool,
+}
+
+/// An element's local name and its attributes by local name, so that
+/// `xlink:href` and `href` look the same.
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+}
+
+struct ManifestItem {
+    href: String,
+    media_type: String,
+}
+
+/// Follows `META-INF/container.xml` to the OPF package and reads the spine.
+///
+/// `read_entry` reads a file out of the EPUB by its path in the archive.
+pub fn read_spine(
+    read_entry: impl Fn(&str) -> Result<Vec<u8>, ComicError>,
+) -> Result<Spine, ComicError> {
+    let package_path = elements(&read_entry(CONTAINER_PATH)?)?
+        .into_iter()
+        .find(|element| element.name == "rootfile")
+        .and_then(|element| element.attributes.get("full-path").cloned())
+        .ok_or_else(|| corrupt("container.xml does not point to a package"))?;
+
+    let package = elements(&read_entry(&package_path)?)?;
+    let package_directory = parent_directory(&package_path);
+
+    let mut manifest = HashMap::new();
+    let mut spine_ids = Vec::new();
+    let mut right_to_left = false;
+
+    for element in &package {
+        match element.name.as_str() {
+            "item" => {
+                if let (Some(id), Some(href)) =
+                    (element.attributes.get("id"), element.attributes.get("href"))
+                {
+                    manifest.insert(
+                        id.clone(),
+                        ManifestItem {
+                            href: resolve_href(package_directory, href),
+                            media_type: element
+                                .attributes
+                                .get("media-type")
+                                .cloned()
+                                .unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+            "spine" => {
+                right_to_left = element
+                    .attributes
+                    .get("page-progression-direction")
+                    .map(String::as_str)
+                    == Some("rtl");
+            }
+            "itemref" => {
+                // Pages the book marks as not part of the reading order are left out.
+                let is_linear = element.attributes.get("linear").map(String::as_str) != Some("no");
+
+                if let Some(idref) = element.attributes.get("idref").filter(|_| is_linear) {
+                    spine_ids.push(idref.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut pages = Vec::new();
+
+    for id in spine_ids {
+        let item = match manifest.get(&id) {
+            Some(item) => item,
+            None => continue,
+        };
+
+        // Most fixed-layout books wrap each image in an XHTML page, some put
+        // the images in the spine directly.
+        let image = if item.media_type.starts_with("image/") {
+            Some(item.href.clone())
+        } else {
+            page_image(&item.href, &read_entry(&item.href)?)?
+        };
+
+        match image {
+            Some(image) if !pages.contains(&image) => pages.push(image),
+            Some(_) => {}
+            None => eprintln!("epub: skipping page without an image {:?}", item.href),
+        }
+    }
+
+    Ok(Spine {
+        pages,
+        package_path,
+        right_to_left,
+    })
+}
+
+/// The first image an XHTML page shows, either as `<img>` or SVG `<image>`.
+fn page_image(page_path: &str, page: &[u8]) -> Result<Option<String>, ComicError> {
+    let page_directory = parent_directory(page_path);
+
+    // Parsing stops at the image, so HTML entities in text further down,
+    // which aren't valid XML, don't get in the way.
+    for element in start_elements(page) {
+        let element = element?;
+
+        let href = match element.name.as_str() {
+            "img" => element.attributes.get("src"),
+            "image" => element.attributes.get("href"),
+            _ => None,
+        };
+
+        if let Some(href) = href {
+            return Ok(Some(resolve_href(page_directory, href)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn elements(document: &[u8]) -> Result<Vec<Element>, ComicError> {
+    start_elements(document).collect()
+}
+
+fn start_elements(document: &[u8]) -> impl Iterator<Item = Result<Element, ComicError>> + '_ {
+    EventReader::new(document)
+        .into_iter()
+        .filter_map(|event| match event {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => Some(Ok(Element {
+                name: name.local_name,
+                attributes: attributes
+                    .into_iter()
+                    .map(|attribute| (attribute.name.local_name, attribute.value))
+                    .collect(),
+            })),
+            Ok(_) => None,
+            Err(error) => Some(Err(corrupt(&error.to_string()))),
+        })
+}
+
+fn parent_directory(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(directory, _)| directory)
+}
+
+/// Turns a link relative to `directory` into a path inside the archive,
+/// dropping any fragment and decoding percent escapes.
+fn resolve_href(directory: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+
+    let mut components: Vec<&str> = if href.starts_with('/') {
+        Vec::new()
+    } else {
+        directory
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .collect()
+    };
+
+    for component in href.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    components.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn corrupt(reason: &str) -> ComicError {
+    ComicError::CorruptArchive(format!("EPUB: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container xmlns="urn:oasis:names:tc:opendocument:xmlns:container" version="1.0">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+    fn read_spine_from(files: &[(&str, &str)]) -> Spine {
+        let files: HashMap<&str, &str> = files.iter().copied().collect();
+
+        read_spine(|path| {
+            files
+                .get(path)
+                .map(|contents| contents.as_bytes().to_vec())
+                .ok_or_else(|| corrupt(&format!("no {}", path)))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn hrefs_are_resolved_against_their_directory() {
+        assert_eq!(
+            resolve_href("OEBPS/Text", "page1.xhtml"),
+            "OEBPS/Text/page1.xhtml"
+        );
+        assert_eq!(resolve_href("", "page1.xhtml"), "page1.xhtml");
+        assert_eq!(
+            resolve_href("OEBPS/Text", "./page1.xhtml#top"),
+            "OEBPS/Text/page1.xhtml"
+        );
+    }
+
+    #[test]
+    fn parent_directories_in_hrefs_are_followed() {
+        assert_eq!(
+            resolve_href("OEBPS/Text", "../Images/01.jpg"),
+            "OEBPS/Images/01.jpg"
+        );
+        assert_eq!(resolve_href("OEBPS/Text", "../../01.jpg"), "01.jpg");
+        assert_eq!(resolve_href("OEBPS", "../../../01.jpg"), "01.jpg");
+        assert_eq!(
+            resolve_href("OEBPS/Text", "/Images/01.jpg"),
+            "Images/01.jpg"
+        );
+    }
+
+    #[test]
+    fn percent_escapes_in_hrefs_are_decoded() {
+        assert_eq!(resolve_href("OEBPS", "Page%2001.jpg"), "OEBPS/Page 01.jpg");
+        assert_eq!(percent_decode("%E3%83%9A%E3%83%BC%E3%82%B8"), "ページ");
+    }
+
+    #[test]
+    fn broken_percent_escapes_are_kept_as_they_are() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz1"), "%zz1");
+        assert_eq!(percent_decode("%2"), "%2");
+    }
+
+    #[test]
+    fn pages_outside_the_reading_order_are_left_out() {
+        let package = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="cover" href="Images/cover.jpg" media-type="image/jpeg"/>
+    <item id="ad" href="Images/ad.jpg" media-type="image/jpeg"/>
+    <item id="page1" href="Text/page%201.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine page-progression-direction="rtl">
+    <itemref idref="cover"/>
+    <itemref idref="ad" linear="no"/>
+    <itemref idref="page1" linear="yes"/>
+  </spine>
+</package>"#;
+        let page = r#"<?xml version="1.0"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><body><img src="../Images/01.jpg"/></body></html>"#;
+
+        let spine = read_spine_from(&[
+            (CONTAINER_PATH, CONTAINER),
+            ("OEBPS/content.opf", package),
+            ("OEBPS/Text/page 1.xhtml", page),
+        ]);
+
+        assert_eq!(
+            spine.pages,
+            vec!["OEBPS/Images/cover.jpg", "OEBPS/Images/01.jpg"]
+        );
+        assert_eq!(spine.package_path, "OEBPS/content.opf");
+        assert!(spine.right_to_left);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use iced::{button, scrollable, Button, Column, Element, Length, Row, Scrollable, Text};
+
+use crate::{comic::ARCHIVE_EXTENSIONS, natural_sort};
+
+#[derive(Debug, Clone)]
+pub enum FileBrowserMessage {
+    /// Show the contents of another directory.
+    Navigate(PathBuf),
+    Open(PathBuf),
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    path: PathBuf,
+    name: String,
+    is_directory: bool,
+    button: button::State,
+}
+
+/// A file picker drawn with iced itself, so opening a comic doesn't depend
+/// on a desktop portal or a native dialog being available.
+///
+/// Only directories and files that look like comics are listed, and the
+/// folder being shown can be opened as a comic itself.
+#[derive(Debug, Clone)]
+pub struct FileBrowser {
+    directory: PathBuf,
+    entries: Vec<Entry>,
+    /// Why the directory couldn't be listed, if it couldn't.
+    error: Option<String>,
+    scroll: scrollable::State,
+    parent_button: button::State,
+    open_directory_button: button::State,
+    cancel_button: button::State,
+}
+
+impl FileBrowser {
+    pub fn new(directory: PathBuf) -> Self {
+        let mut file_browser = Self {
+            directory: PathBuf::new(),
+            entries: Vec::new(),
+            error: None,
+            scroll: scrollable::State::new(),
+            parent_button: button::State::new(),
+            open_directory_button: button::State::new(),
+            cancel_button: button::State::new(),
+        };
+
+        file_browser.navigate(directory);
+
+        file_browser
+    }
+
+    pub fn navigate(&mut self, directory: PathBuf) {
+        self.scroll = scrollable::State::new();
+
+        match list_directory(&directory) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(error) => {
+                self.entries = Vec::new();
+                self.error = Some(error.to_string());
+            }
+        }
+
+        self.directory = directory;
+    }
+
+    pub fn view(&mut self) -> Element<'_, FileBrowserMessage> {
+        let mut header = Row::new().spacing(10).push(
+            Button::new(&mut self.cancel_button, Text::new("Cancel"))
+                .on_press(FileBrowserMessage::Cancel),
+        );
+
+        let parent_button = Button::new(&mut self.parent_button, Text::new("Up"));
+
+        header = match self.directory.parent() {
+            Some(parent) => header
+                .push(parent_button.on_press(FileBrowserMessage::Navigate(parent.to_path_buf()))),
+            None => header.push(parent_button),
+        };
+
+        // A folder of loose images is a comic too.
+        header = header
+            .push(
+                Button::new(&mut self.open_directory_button, Text::new("Open Folder"))
+                    .on_press(FileBrowserMessage::Open(self.directory.clone())),
+            )
+            .push(Text::new(self.directory.to_string_lossy()).size(20));
+
+        let mut listing = Scrollable::new(&mut self.scroll)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .spacing(2);
+
+        if let Some(error) = &self.error {
+            listing =
+                listing.push(Text::new(format!("Could not open this folder: {}", error)).size(16));
+        } else if self.entries.is_empty() {
+            listing = listing.push(Text::new("No comics in this folder").size(16));
+        }
+
+        for entry in &mut self.entries {
+            let (label, message) = if entry.is_directory {
+                (
+                    format!("{}/", entry.name),
+                    FileBrowserMessage::Navigate(entry.path.clone()),
+                )
+            } else {
+                (
+                    entry.name.clone(),
+                    FileBrowserMessage::Open(entry.path.clone()),
+                )
+            };
+
+            listing = listing.push(
+                Button::new(&mut entry.button, Text::new(label).size(18))
+                    .width(Length::Fill)
+                    .on_press(message),
+            );
+        }
+
+        Column::new()
+            .padding(20)
+            .spacing(10)
+            .push(header)
+            .push(listing)
+            .into()
+    }
+}
+
+/// Subdirectories first, then comics, each in natural order. Hidden files
+/// are left out.
+fn list_directory(directory: &Path) -> std::io::Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = fs::read_dir(directory)?
+        .filter_map(Result::ok)
+        .filter_map(|dir_entry| {
+            let path = dir_entry.path();
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+
+            if name.starts_with('.') {
+                return None;
+            }
+
+            // Follows symlinks, so a linked folder can be browsed into.
+            let is_directory = path.is_dir();
+
+            if !is_directory && !is_comic_file(&path) {
+                return None;
+            }
+
+            Some(Entry {
+                path,
+                name,
+                is_directory,
+                button: button::State::new(),
+            })
+        })
+        .collect();
+
+    entries.sort_by(|a, b| {
+        b.is_directory
+            .cmp(&a.is_directory)
+            .then_with(|| natural_sort::compare(&a.name, &b.name))
+    });
+
+    Ok(entries)
+}
+
+fn is_comic_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            ARCHIVE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+        })
+}
@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use iced::{
+    container, text_input, Align, Background, Color, Column, Container, Element, Length, Text,
+    TextInput,
+};
+
+/// How long the HUD stays on screen after what it shows last changed.
+pub const HUD_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub enum GoToPageMessage {
+    InputChanged(String),
+    Submit,
+}
+
+/// Asks where in the comic to go, as a page number or a percentage.
+#[derive(Debug, Clone)]
+pub struct GoToPageDialog {
+    input: String,
+    input_state: text_input::State,
+    /// Why the last thing typed in isn't somewhere to go.
+    error: Option<String>,
+}
+
+impl GoToPageDialog {
+    pub fn new() -> Self {
+        Self {
+            input: String::new(),
+            input_state: text_input::State::focused(),
+            error: None,
+        }
+    }
+
+    pub fn set_input(&mut self, input: String) {
+        self.input = input;
+        self.error = None;
+    }
+
+    /// The page typed in, counted from zero, or `None` with the reason shown
+    /// under the input.
+    pub fn target(&mut self, page_count: usize) -> Option<usize> {
+        match parse_page_target(&self.input, page_count) {
+            Ok(index) => Some(index),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+
+    pub fn view(&mut self, page_count: usize) -> Element<'_, GoToPageMessage> {
+        let input = TextInput::new(
+            &mut self.input_state,
+            "Page or percentage",
+            &self.input,
+            GoToPageMessage::InputChanged,
+        )
+        .width(Length::Units(240))
+        .padding(8)
+        .size(20)
+        .on_submit(GoToPageMessage::Submit);
+
+        let hint = match &self.error {
+            Some(error) => error.clone(),
+            None => format!("1 to {}, or 0% to 100%", page_count),
+        };
+
+        panel(
+            Column::new()
+                .spacing(8)
+                .push(Text::new("Go to page").size(16))
+                .push(input)
+                .push(Text::new(hint).size(14)),
+        )
+    }
+}
+
+/// A line about where the reader is, shown over the top of the pages.
+pub fn status<'a, Message: 'a>(text: String) -> Element<'a, Message> {
+    panel(Text::new(text).size(16))
+}
+
+/// A warning about the comic, at the bottom of the pages and in colour so
+/// that it isn't missed the way a line in the HUD could be.
+pub fn warning<'a, Message: 'a>(text: String) -> Element<'a, Message> {
+    Container::new(
+        Container::new(Text::new(text).size(20))
+            .padding(12)
+            .style(WarningStyle),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding(16)
+    .center_x()
+    .align_y(Align::End)
+    .into()
+}
+
+/// Turns what was typed into the go-to-page dialog into a page index.
+///
+/// A plain number is a page counted from 1, as the HUD shows them. A number
+/// followed by `%` is that far through the comic, `0%` being the first page
+/// and `100%` the last.
+pub fn parse_page_target(input: &str, page_count: usize) -> Result<usize, String> {
+    let input = input.trim();
+
+    if page_count == 0 {
+        return Err("The comic has no pages".to_string());
+    }
+
+    if let Some(percent) = input.strip_suffix('%') {
+        let percent: f32 = percent
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} is not a percentage", input))?;
+
+        if !(0.0..=100.0).contains(&percent) {
+            return Err("Percentages go from 0% to 100%".to_string());
+        }
+
+        return Ok(((page_count - 1) as f32 * percent / 100.0).round() as usize);
+    }
+
+    let page: usize = input
+        .parse()
+        .map_err(|_| format!("{:?} is not a page number", input))?;
+
+    if page == 0 || page > page_count {
+        return Err(format!("Pages go from 1 to {}", page_count));
+    }
+
+    Ok(page - 1)
+}
+
+/// Puts `content` on a dark box at the top middle of the area it is given.
+fn panel<'a, Message: 'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    Container::new(Container::new(content).padding(12).style(PanelStyle))
+        .width(Length::Fill)
+        .padding(16)
+        .center_x()
+        .into()
+}
+
+/// Light text on a dark box, readable over any page.
+struct PanelStyle;
+
+impl container::StyleSheet for PanelStyle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(Color::WHITE),
+            background: Some(Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.75))),
+            border_radius: 6.0,
+            ..container::Style::default()
+        }
+    }
+}
+
+/// Dark text on amber, the colour of a warning sign.
+struct WarningStyle;
+
+impl container::StyleSheet for WarningStyle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(Color::BLACK),
+            background: Some(Background::Color(Color::from_rgb(1.0, 0.75, 0.0))),
+            border_radius: 6.0,
+            ..container::Style::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_numbers_count_from_one() {
+        assert_eq!(parse_page_target("1", 20), Ok(0));
+        assert_eq!(parse_page_target(" 20 ", 20), Ok(19));
+    }
+
+    #[test]
+    fn page_zero_is_refused() {
+        assert_eq!(
+            parse_page_target("0", 20),
+            Err("Pages go from 1 to 20".to_string())
+        );
+    }
+
+    #[test]
+    fn pages_past_the_end_are_refused() {
+        assert_eq!(
+            parse_page_target("21", 20),
+            Err("Pages go from 1 to 20".to_string())
+        );
+    }
+
+    #[test]
+    fn percentages_go_through_the_comic() {
+        assert_eq!(parse_page_target("0%", 21), Ok(0));
+        assert_eq!(parse_page_target("50%", 21), Ok(10));
+        assert_eq!(parse_page_target("100%", 21), Ok(20));
+    }
+
+    #[test]
+    fn percentages_out_of_range_are_refused() {
+        assert!(parse_page_target("101%", 20).is_err());
+        assert!(parse_page_target("-1%", 20).is_err());
+    }
+
+    #[test]
+    fn nan_is_not_a_percentage() {
+        assert!(parse_page_target("nan%", 20).is_err());
+        assert!(parse_page_target("NaN %", 20).is_err());
+    }
+
+    #[test]
+    fn words_are_not_pages() {
+        assert_eq!(
+            parse_page_target("ten", 20),
+            Err("\"ten\" is not a page number".to_string())
+        );
+    }
+
+    #[test]
+    fn a_comic_without_pages_has_nowhere_to_go() {
+        assert!(parse_page_target("1", 0).is_err());
+    }
+}
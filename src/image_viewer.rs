@@ -1,9 +1,80 @@
 // based off of https://github.com/iced-rs/iced/blob/master/native/src/widget/image/viewer.rs
+use iced_native::{
+    clipboard::Clipboard, event, layout, mouse, Align, Element, Event, Hasher, Layout, Length,
+    Point, Rectangle, Size, Vector, Widget,
+};
 use std::hash::Hash;
-use iced_native::{Element, Event, Hasher, Layout, Length, Point, Rectangle, Size, Vector, Widget, clipboard::Clipboard, event, layout, mouse};
+
+const DEFAULT_MIN_SCALE: f32 = 0.25;
+const DEFAULT_MAX_SCALE: f32 = 10.0;
+const DEFAULT_SCALE_STEP: f32 = 0.10;
+
+/// How an image is sized to the viewer before any zoom is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitMode {
+    /// Shrink the whole image into view, but never enlarge it.
+    Best,
+    Width,
+    Height,
+    /// One image pixel per screen pixel.
+    ActualSize,
+    /// Fill the viewer, ignoring the aspect ratio.
+    Stretch,
+}
+
+impl Default for FitMode {
+    fn default() -> Self {
+        FitMode::Best
+    }
+}
+
+impl FitMode {
+    const ALL: [FitMode; 5] = [
+        FitMode::Best,
+        FitMode::Width,
+        FitMode::Height,
+        FitMode::ActualSize,
+        FitMode::Stretch,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FitMode::Best => "best",
+            FitMode::Width => "width",
+            FitMode::Height => "height",
+            FitMode::ActualSize => "actual_size",
+            FitMode::Stretch => "stretch",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        FitMode::ALL
+            .iter()
+            .find(|fit_mode| fit_mode.name() == name)
+            .copied()
+    }
+
+    /// Horizontal and vertical factors that fit an image of `image` size into `bounds`.
+    fn ratios(&self, image: Size, bounds: Size) -> (f32, f32) {
+        let width_ratio = bounds.width / image.width;
+        let height_ratio = bounds.height / image.height;
+
+        match self {
+            FitMode::Best => {
+                let ratio = width_ratio.min(height_ratio).min(1.0);
+                (ratio, ratio)
+            }
+            FitMode::Width => (width_ratio, width_ratio),
+            FitMode::Height => (height_ratio, height_ratio),
+            FitMode::ActualSize => (1.0, 1.0),
+            FitMode::Stretch => (width_ratio, height_ratio),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct ImageViewerState {
+    fit_mode: FitMode,
     scale: f32,
     starting_offset: Vector,
     current_offset: Vector,
@@ -13,6 +84,7 @@ pub struct ImageViewerState {
 impl Default for ImageViewerState {
     fn default() -> Self {
         Self {
+            fit_mode: FitMode::default(),
             scale: 1.0,
             starting_offset: Vector::default(),
             current_offset: Vector::default(),
@@ -22,8 +94,17 @@ impl Default for ImageViewerState {
 }
 
 impl ImageViewerState {
-    pub fn new() -> Self {
-        ImageViewerState::default()
+    pub fn with_fit_mode(fit_mode: FitMode) -> Self {
+        Self {
+            fit_mode,
+            ..ImageViewerState::default()
+        }
+    }
+
+    /// Switches to a different fit, dropping any zoom made relative to the old one.
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode) {
+        self.fit_mode = fit_mode;
+        self.reset();
     }
 
     fn offset(&self, bounds: Rectangle, image_size: Size) -> Vector {
@@ -40,6 +121,79 @@ impl ImageViewerState {
     pub fn is_cursor_grabbed(&self) -> bool {
         self.cursor_grabbed_at.is_some()
     }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.scale = (self.scale * (1.0 + DEFAULT_SCALE_STEP)).min(DEFAULT_MAX_SCALE);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.scale = (self.scale / (1.0 + DEFAULT_SCALE_STEP)).max(DEFAULT_MIN_SCALE);
+    }
+
+    /// Returns to the fitted scale with the page centered.
+    pub fn reset(&mut self) {
+        *self = ImageViewerState::with_fit_mode(self.fit_mode);
+    }
+
+    /// The part of the image in view, in fractions of its width and height
+    /// from its top left corner, when an image `image` pixels big is shown
+    /// in a viewer `bounds` big.
+    pub fn viewport(&self, image: Size, bounds: Size) -> Rectangle {
+        let image_size = self.image_size(image, bounds);
+        let offset = self.offset(Rectangle::with_size(bounds), image_size);
+
+        let width = (bounds.width / image_size.width).min(1.0);
+        let height = (bounds.height / image_size.height).min(1.0);
+
+        // The offset is how far the middle of the view is from the middle of the image.
+        let center_x = 0.5 + offset.x / image_size.width;
+        let center_y = 0.5 + offset.y / image_size.height;
+
+        Rectangle {
+            x: (center_x - width / 2.0).max(0.0).min(1.0 - width),
+            y: (center_y - height / 2.0).max(0.0).min(1.0 - height),
+            width,
+            height,
+        }
+    }
+
+    /// Zooms to `scale` and pans to have the middle of `viewport`, given as
+    /// by [`ImageViewerState::viewport`], in the middle of the view.
+    pub fn show_viewport(&mut self, scale: f32, viewport: Rectangle, image: Size, bounds: Size) {
+        self.scale = scale.clamp(DEFAULT_MIN_SCALE, DEFAULT_MAX_SCALE);
+
+        let image_size = self.image_size(image, bounds);
+        let center = viewport.center();
+
+        self.current_offset = Vector::new(
+            if image_size.width > bounds.width {
+                (center.x - 0.5) * image_size.width
+            } else {
+                0.0
+            },
+            if image_size.height > bounds.height {
+                (center.y - 0.5) * image_size.height
+            } else {
+                0.0
+            },
+        );
+        self.starting_offset = self.current_offset;
+    }
+
+    /// How big an image `dimensions` pixels big is drawn in a viewer `bounds` big.
+    fn image_size(&self, dimensions: Size, bounds: Size) -> Size {
+        // Zoom is applied on top of the fit, so 100% always means "as fitted".
+        let (width_ratio, height_ratio) = self.fit_mode.ratios(dimensions, bounds);
+
+        Size::new(
+            dimensions.width * width_ratio * self.scale,
+            dimensions.height * height_ratio * self.scale,
+        )
+    }
 }
 
 pub struct ImageViewer<'a> {
@@ -50,6 +204,7 @@ pub struct ImageViewer<'a> {
     min_scale: f32,
     max_scale: f32,
     scale_step: f32,
+    horizontal_alignment: Align,
     handle: iced::image::Handle,
 }
 
@@ -60,9 +215,10 @@ impl<'a> ImageViewer<'a> {
             padding: 0,
             width: Length::Shrink,
             height: Length::Shrink,
-            min_scale: 0.25,
-            max_scale: 10.0,
-            scale_step: 0.10,
+            min_scale: DEFAULT_MIN_SCALE,
+            max_scale: DEFAULT_MAX_SCALE,
+            scale_step: DEFAULT_SCALE_STEP,
+            horizontal_alignment: Align::Center,
             handle,
         }
     }
@@ -92,30 +248,21 @@ impl<'a> ImageViewer<'a> {
         self
     }
 
+    /// Where the image sits horizontally when it is narrower than the viewer,
+    /// e.g. against the gutter when two pages are shown side by side.
+    pub fn align_x(mut self, alignment: Align) -> Self {
+        self.horizontal_alignment = alignment;
+        self
+    }
+
     fn image_size<Renderer>(&self, renderer: &Renderer, bounds: Size) -> Size
     where
         Renderer: self::Renderer + iced_native::image::Renderer,
     {
         let (width, height) = renderer.dimensions(&self.handle);
 
-        let (width, height) = {
-            let dimensions = (width as f32, height as f32);
-
-            let width_ratio = bounds.width / dimensions.0;
-            let height_ratio = bounds.height / dimensions.1;
-
-            let ratio = width_ratio.min(height_ratio);
-
-            let scale = self.state.scale;
-
-            if ratio < 1.0 {
-                (dimensions.0 * ratio * scale, dimensions.1 * ratio * scale)
-            } else {
-                (dimensions.0 * scale, dimensions.1 * scale)
-            }
-        };
-
-        Size::new(width, height)
+        self.state
+            .image_size(Size::new(width as f32, height as f32), bounds)
     }
 }
 
@@ -282,10 +429,15 @@ where
         let image_size = self.image_size(renderer, bounds.size());
 
         let translation = {
-            let image_top_left = Vector::new(
-                bounds.width / 2.0 - image_size.width / 2.0,
-                bounds.height / 2.0 - image_size.height / 2.0,
-            );
+            let free_width = bounds.width - image_size.width;
+
+            let left = match self.horizontal_alignment {
+                Align::Start if free_width > 0.0 => 0.0,
+                Align::End if free_width > 0.0 => free_width,
+                _ => free_width / 2.0,
+            };
+
+            let image_top_left = Vector::new(left, bounds.height / 2.0 - image_size.height / 2.0);
 
             image_top_left - self.state.offset(bounds, image_size)
         };
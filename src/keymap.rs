@@ -0,0 +1,445 @@
+use std::{collections::HashMap, fmt, fs, path::PathBuf};
+
+use iced_native::keyboard::{KeyCode, Modifiers};
+use thiserror::Error;
+
+use crate::app::Action;
+
+const KEYMAP_FILE_NAME: &str = "keymap.toml";
+
+const DEFAULT_KEYMAP: &str = r#"# comik keymap
+#
+# Each action is bound to a list of key chords. A chord is a key name,
+# optionally prefixed by modifiers joined with '+', e.g. "Ctrl+Shift+O".
+# Modifiers: Ctrl, Shift, Alt, Logo
+#
+# Actions left out of this file keep their default keys, and a chord bound
+# here takes over from the action it is bound to by default.
+#
+# Actions: next_page, previous_page, first_page, last_page, go_to_page,
+# page_left, page_right, zoom_in, zoom_out, fit_page, fit_width, fit_height,
+# actual_size, stretch, cycle_page_order, toggle_spread_mode,
+# toggle_right_to_left, toggle_continuous_mode, toggle_info,
+# toggle_page_navigator, toggle_bookmarks, toggle_fullscreen, open_file,
+# toggle_library, quit
+#
+# page_left and page_right follow the reading direction, so they turn
+# forwards and backwards the other way round when reading right to left.
+#
+# In continuous mode, where all pages are stacked in one long strip, turning
+# the page scrolls down or up by most of a window instead.
+#
+# The fit actions pick how pages are sized to the window and are remembered
+# for each comic. Zooming is relative to the fitted size.
+
+next_page = ["Down", "PageDown", "Space", "J"]
+previous_page = ["Up", "PageUp", "Backspace", "Shift+Space", "K"]
+first_page = ["Home", "G"]
+last_page = ["End", "Shift+G"]
+go_to_page = ["Ctrl+G"]
+page_left = ["Left", "H"]
+page_right = ["Right", "L"]
+zoom_in = ["Plus", "Equals", "NumpadAdd"]
+zoom_out = ["Minus", "NumpadSubtract"]
+fit_page = ["Key0", "Numpad0"]
+fit_width = ["Key2", "W"]
+fit_height = ["Key3"]
+actual_size = ["Key1", "Numpad1"]
+stretch = ["Key4"]
+cycle_page_order = ["S"]
+toggle_spread_mode = ["D"]
+toggle_right_to_left = ["R"]
+toggle_continuous_mode = ["C"]
+toggle_info = ["I"]
+toggle_page_navigator = ["T"]
+toggle_bookmarks = ["M"]
+toggle_fullscreen = ["F", "F11"]
+open_file = ["O", "Ctrl+O"]
+toggle_library = ["B"]
+quit = ["Q", "Ctrl+Q"]
+"#;
+
+/// Key names as they are written in the keymap file.
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("Key0", KeyCode::Key0),
+    ("Key1", KeyCode::Key1),
+    ("Key2", KeyCode::Key2),
+    ("Key3", KeyCode::Key3),
+    ("Key4", KeyCode::Key4),
+    ("Key5", KeyCode::Key5),
+    ("Key6", KeyCode::Key6),
+    ("Key7", KeyCode::Key7),
+    ("Key8", KeyCode::Key8),
+    ("Key9", KeyCode::Key9),
+    ("Numpad0", KeyCode::Numpad0),
+    ("Numpad1", KeyCode::Numpad1),
+    ("Numpad2", KeyCode::Numpad2),
+    ("Numpad3", KeyCode::Numpad3),
+    ("Numpad4", KeyCode::Numpad4),
+    ("Numpad5", KeyCode::Numpad5),
+    ("Numpad6", KeyCode::Numpad6),
+    ("Numpad7", KeyCode::Numpad7),
+    ("Numpad8", KeyCode::Numpad8),
+    ("Numpad9", KeyCode::Numpad9),
+    ("NumpadAdd", KeyCode::NumpadAdd),
+    ("NumpadSubtract", KeyCode::NumpadSubtract),
+    ("NumpadEnter", KeyCode::NumpadEnter),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Insert", KeyCode::Insert),
+    ("Delete", KeyCode::Delete),
+    ("Space", KeyCode::Space),
+    ("Backspace", KeyCode::Backspace),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Escape", KeyCode::Escape),
+    ("Plus", KeyCode::Plus),
+    ("Minus", KeyCode::Minus),
+    ("Equals", KeyCode::Equals),
+    ("Comma", KeyCode::Comma),
+    ("Period", KeyCode::Period),
+    ("Slash", KeyCode::Slash),
+    ("Backslash", KeyCode::Backslash),
+    ("Semicolon", KeyCode::Semicolon),
+    ("Apostrophe", KeyCode::Apostrophe),
+    ("LBracket", KeyCode::LBracket),
+    ("RBracket", KeyCode::RBracket),
+];
+
+#[derive(Error, Debug, Clone)]
+pub enum KeymapError {
+    #[error("could not read keymap: {0}")]
+    Io(String),
+    #[error("keymap is not valid TOML: {0}")]
+    Syntax(String),
+    #[error("unknown action `{0}`")]
+    UnknownAction(String),
+    #[error("bindings for `{0}` must be a list of key chords")]
+    InvalidBindingList(String),
+    #[error("unknown key `{key}` in chord `{chord}`")]
+    UnknownKey { chord: String, key: String },
+    #[error("unknown modifier `{modifier}` in chord `{chord}`")]
+    UnknownModifier { chord: String, modifier: String },
+    #[error("chord `{chord}` is bound to both `{first}` and `{second}`")]
+    DuplicateChord {
+        chord: String,
+        first: String,
+        second: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    key_code: KeyCode,
+    shift: bool,
+    control: bool,
+    alt: bool,
+    logo: bool,
+}
+
+impl KeyChord {
+    pub fn new(key_code: KeyCode, modifiers: Modifiers) -> Self {
+        Self {
+            key_code,
+            shift: modifiers.shift,
+            control: modifiers.control,
+            alt: modifiers.alt,
+            logo: modifiers.logo,
+        }
+    }
+
+    fn parse(chord: &str) -> Result<Self, KeymapError> {
+        let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+
+        // A trailing empty part means the key itself is '+', e.g. "Ctrl++".
+        let key = match parts.pop() {
+            Some("") if parts.last() == Some(&"") => {
+                parts.pop();
+                "Plus"
+            }
+            Some(key) => key,
+            None => "",
+        };
+
+        let key_code = KEY_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, key_code)| *key_code)
+            .ok_or_else(|| KeymapError::UnknownKey {
+                chord: chord.to_string(),
+                key: key.to_string(),
+            })?;
+
+        let mut modifiers = Modifiers::default();
+
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.control = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                "logo" | "super" | "cmd" => modifiers.logo = true,
+                _ => {
+                    return Err(KeymapError::UnknownModifier {
+                        chord: chord.to_string(),
+                        modifier: modifier.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(Self::new(key_code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.control, "Ctrl"),
+            (self.shift, "Shift"),
+            (self.alt, "Alt"),
+            (self.logo, "Logo"),
+        ];
+
+        for (_, name) in modifiers.iter().filter(|(pressed, _)| *pressed) {
+            write!(f, "{}+", name)?;
+        }
+
+        match KEY_NAMES
+            .iter()
+            .find(|(_, key_code)| *key_code == self.key_code)
+        {
+            Some((name, _)) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self.key_code),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::parse(DEFAULT_KEYMAP, HashMap::new()).expect("default keymap is valid")
+    }
+}
+
+impl Keymap {
+    pub fn action_for(&self, key_code: KeyCode, modifiers: Modifiers) -> Option<Action> {
+        self.bindings
+            .get(&KeyChord::new(key_code, modifiers))
+            .copied()
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("comik").join(KEYMAP_FILE_NAME))
+    }
+
+    /// Loads the user keymap over the default one, writing out the default one
+    /// if none exists yet.
+    ///
+    /// Any problem with the file is reported back alongside the default keymap,
+    /// so a typo never leaves the reader without working keys.
+    pub fn load() -> (Self, Vec<KeymapError>) {
+        let path = match Keymap::config_path() {
+            Some(path) => path,
+            None => return (Keymap::default(), Vec::new()),
+        };
+
+        if !path.exists() {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, DEFAULT_KEYMAP));
+
+            return match written {
+                Ok(_) => (Keymap::default(), Vec::new()),
+                Err(error) => (Keymap::default(), vec![KeymapError::Io(error.to_string())]),
+            };
+        }
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) => return (Keymap::default(), vec![KeymapError::Io(error.to_string())]),
+        };
+
+        match Keymap::parse(&contents, Keymap::default().bindings) {
+            Ok(keymap) => (keymap, Vec::new()),
+            Err(errors) => (Keymap::default(), errors),
+        }
+    }
+
+    /// Parses a keymap file over `defaults`. Every action the file lists is
+    /// bound to the chords listed for it instead of its default ones, so
+    /// actions added since the file was written still have keys.
+    fn parse(
+        contents: &str,
+        defaults: HashMap<KeyChord, Action>,
+    ) -> Result<Self, Vec<KeymapError>> {
+        let table = match contents.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => return Err(vec![KeymapError::Syntax("expected a table".to_string())]),
+            Err(error) => return Err(vec![KeymapError::Syntax(error.to_string())]),
+        };
+
+        let mut bindings = defaults;
+        // Only chords bound twice within the file itself are a mistake.
+        let mut listed = HashMap::new();
+        let mut errors = Vec::new();
+
+        for (action_name, chords) in table.iter() {
+            let action = match Action::from_name(action_name) {
+                Some(action) => action,
+                None => {
+                    errors.push(KeymapError::UnknownAction(action_name.clone()));
+                    continue;
+                }
+            };
+
+            let chords = match chords.as_array() {
+                Some(chords) => chords,
+                None => {
+                    errors.push(KeymapError::InvalidBindingList(action_name.clone()));
+                    continue;
+                }
+            };
+
+            bindings.retain(|_, bound| *bound != action);
+
+            for chord in chords {
+                let chord = match chord.as_str() {
+                    Some(chord) => chord,
+                    None => {
+                        errors.push(KeymapError::InvalidBindingList(action_name.clone()));
+                        continue;
+                    }
+                };
+
+                match KeyChord::parse(chord) {
+                    Ok(key_chord) => {
+                        bindings.insert(key_chord, action);
+
+                        if let Some(existing) = listed.insert(key_chord, action) {
+                            if existing != action {
+                                errors.push(KeymapError::DuplicateChord {
+                                    chord: key_chord.to_string(),
+                                    first: existing.name().to_string(),
+                                    second: action.name().to_string(),
+                                });
+                            }
+                        }
+                    }
+                    Err(error) => errors.push(error),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self { bindings })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_keymap(contents: &str) -> Keymap {
+        Keymap::parse(contents, Keymap::default().bindings).expect("keymap is valid")
+    }
+
+    fn action(keymap: &Keymap, chord: &str) -> Option<Action> {
+        keymap
+            .bindings
+            .get(&KeyChord::parse(chord).expect("chord is valid"))
+            .copied()
+    }
+
+    #[test]
+    fn actions_left_out_keep_their_default_keys() {
+        let keymap = user_keymap(r#"next_page = ["N"]"#);
+
+        assert_eq!(action(&keymap, "N"), Some(Action::NextPage));
+        assert_eq!(action(&keymap, "Down"), None);
+        assert_eq!(action(&keymap, "K"), Some(Action::PreviousPage));
+        assert_eq!(action(&keymap, "M"), Some(Action::ToggleBookmarks));
+    }
+
+    #[test]
+    fn user_chords_take_over_from_default_actions() {
+        let keymap = user_keymap(r#"quit = ["D"]"#);
+
+        assert_eq!(action(&keymap, "D"), Some(Action::Quit));
+        assert_eq!(action(&keymap, "Q"), None);
+        assert_eq!(action(&keymap, "Ctrl+Q"), None);
+    }
+
+    #[test]
+    fn empty_list_unbinds_an_action() {
+        let keymap = user_keymap("zoom_in = []");
+
+        assert_eq!(action(&keymap, "Plus"), None);
+        assert_eq!(action(&keymap, "Minus"), Some(Action::ZoomOut));
+    }
+
+    #[test]
+    fn chord_bound_twice_in_the_file_is_an_error() {
+        let errors = Keymap::parse(
+            "next_page = [\"N\"]\nprevious_page = [\"N\"]",
+            Keymap::default().bindings,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            errors.as_slice(),
+            [KeymapError::DuplicateChord { .. }]
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rusqlite::{params, Connection};
+
+use image::DynamicImage;
+
+use crate::{
+    comic::{Comic, ComicError, ARCHIVE_EXTENSIONS},
+    natural_sort, thumbnail,
+};
+
+const LIBRARY_DATABASE_FILE_NAME: &str = "library.sqlite3";
+const LIBRARY_CONFIG_FILE_NAME: &str = "library.toml";
+
+const DEFAULT_LIBRARY_CONFIG: &str = r#"# comik library
+#
+# Folders that are searched for comics, including their subfolders, e.g.
+#
+# roots = ["/home/me/Comics", "/mnt/nas/Manga"]
+
+roots = []
+"#;
+
+/// A comic found in one of the library's folders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub title: String,
+    pub series: Option<String>,
+    pub page_count: usize,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Modification time of the file in seconds since the Unix epoch.
+    pub modified: u64,
+}
+
+/// What changed on disk since the index was last brought up to date.
+#[derive(Debug, Clone, Default)]
+pub struct Scan {
+    /// Comics that are new or were changed, read again from scratch.
+    pub updated: Vec<LibraryEntry>,
+    /// Comics that are gone from their folder.
+    pub removed: Vec<PathBuf>,
+    /// Files that couldn't be read, with their size and modification time,
+    /// so they are only tried again once they change.
+    pub failed: Vec<(PathBuf, (u64, u64))>,
+}
+
+/// The index of every comic in the library folders, kept in an SQLite
+/// database so the folders don't have to be read again on every start.
+#[derive(Debug)]
+pub struct Library {
+    connection: Connection,
+}
+
+impl Library {
+    pub fn data_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("comik").join(LIBRARY_DATABASE_FILE_NAME))
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("comik").join(LIBRARY_CONFIG_FILE_NAME))
+    }
+
+    /// Opens the index, creating it if this is the first time. Without a
+    /// data directory the index only lives as long as the process.
+    pub fn open() -> anyhow::Result<Self> {
+        let connection = match Library::data_path() {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                Connection::open(path)?
+            }
+            None => Connection::open_in_memory()?,
+        };
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS comics (
+                path TEXT PRIMARY KEY NOT NULL,
+                title TEXT NOT NULL,
+                series TEXT,
+                page_count INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS unreadable (
+                path TEXT PRIMARY KEY NOT NULL,
+                size INTEGER NOT NULL,
+                modified INTEGER NOT NULL
+            );",
+        )?;
+
+        Ok(Self { connection })
+    }
+
+    /// The folders to search for comics, read from the library config. The
+    /// config is written out with an explanation if it doesn't exist yet.
+    pub fn roots() -> Vec<PathBuf> {
+        let path = match Library::config_path() {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+
+        if !path.exists() {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, DEFAULT_LIBRARY_CONFIG));
+
+            if let Err(error) = written {
+                eprintln!("library: could not write {:?}: {}", path, error);
+            }
+
+            return Vec::new();
+        }
+
+        let config = match fs::read_to_string(&path).map(|contents| contents.parse::<toml::Value>())
+        {
+            Ok(Ok(config)) => config,
+            Ok(Err(error)) => {
+                eprintln!("library: {}", error);
+                return Vec::new();
+            }
+            Err(error) => {
+                eprintln!("library: could not read {:?}: {}", path, error);
+                return Vec::new();
+            }
+        };
+
+        config
+            .get("roots")
+            .and_then(toml::Value::as_array)
+            .map(|roots| {
+                roots
+                    .iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(PathBuf::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every indexed comic, grouped by series and in natural order.
+    pub fn entries(&self) -> anyhow::Result<Vec<LibraryEntry>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT path, title, series, page_count, size, modified FROM comics")?;
+
+        let mut entries = statement
+            .query_map(params![], |row| {
+                Ok(LibraryEntry {
+                    path: PathBuf::from(row.get::<_, String>(0)?),
+                    title: row.get(1)?,
+                    series: row.get(2)?,
+                    page_count: row.get::<_, i64>(3)? as usize,
+                    size: row.get::<_, i64>(4)? as u64,
+                    modified: row.get::<_, i64>(5)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        entries.sort_by(|a, b| {
+            let a_series = a.series.as_deref().unwrap_or(&a.title);
+            let b_series = b.series.as_deref().unwrap_or(&b.title);
+
+            natural_sort::compare(a_series, b_series)
+                .then_with(|| natural_sort::compare(&a.title, &b.title))
+        });
+
+        Ok(entries)
+    }
+
+    /// The size and modification time of every indexed comic, and of every
+    /// file that couldn't be read, for telling which files changed since.
+    pub fn stamps(&self) -> anyhow::Result<HashMap<PathBuf, (u64, u64)>> {
+        let mut stamps: HashMap<PathBuf, (u64, u64)> = self
+            .entries()?
+            .into_iter()
+            .map(|entry| (entry.path, (entry.size, entry.modified)))
+            .collect();
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT path, size, modified FROM unreadable")?;
+
+        let unreadable = statement.query_map(params![], |row| {
+            Ok((
+                PathBuf::from(row.get::<_, String>(0)?),
+                (row.get::<_, i64>(1)? as u64, row.get::<_, i64>(2)? as u64),
+            ))
+        })?;
+
+        for stamp in unreadable {
+            let (path, stamp) = stamp?;
+            stamps.insert(path, stamp);
+        }
+
+        Ok(stamps)
+    }
+
+    /// Writes the result of a scan to the index in one transaction.
+    pub fn apply_scan(&mut self, scan: &Scan) -> anyhow::Result<()> {
+        let transaction = self.connection.transaction()?;
+
+        for path in &scan.removed {
+            transaction.execute("DELETE FROM comics WHERE path = ?1", params![key(path)])?;
+            transaction.execute("DELETE FROM unreadable WHERE path = ?1", params![key(path)])?;
+        }
+
+        // A comic that can't be read anymore has nothing left to show.
+        for (path, (size, modified)) in &scan.failed {
+            transaction.execute("DELETE FROM comics WHERE path = ?1", params![key(path)])?;
+            transaction.execute(
+                "INSERT OR REPLACE INTO unreadable (path, size, modified) VALUES (?1, ?2, ?3)",
+                params![key(path), *size as i64, *modified as i64],
+            )?;
+        }
+
+        for entry in &scan.updated {
+            transaction.execute(
+                "DELETE FROM unreadable WHERE path = ?1",
+                params![key(&entry.path)],
+            )?;
+
+            transaction.execute(
+                "INSERT OR REPLACE INTO comics (path, title, series, page_count, size, modified)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    key(&entry.path),
+                    entry.title,
+                    entry.series,
+                    entry.page_count as i64,
+                    entry.size as i64,
+                    entry.modified as i64,
+                ],
+            )?;
+        }
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+/// Searches the library folders for comics, reading only those that are new
+/// or whose size or modification time differ from `stamps`.
+///
+/// Comics in a folder that can't be read right now, such as a network share
+/// that isn't mounted, are kept rather than dropped from the index.
+pub async fn scan(roots: Vec<PathBuf>, mut stamps: HashMap<PathBuf, (u64, u64)>) -> Scan {
+    let mut scan = Scan::default();
+    let mut files = Vec::new();
+    let mut readable_roots = Vec::new();
+
+    for root in &roots {
+        match collect_comic_files(root, &mut files) {
+            Ok(()) => readable_roots.push(root.clone()),
+            Err(error) => eprintln!("library: could not read {:?}: {}", root, error),
+        }
+    }
+
+    for (path, stamp) in files {
+        if stamps.remove(&path) == Some(stamp) {
+            continue;
+        }
+
+        let summary = match Comic::summarize(path.clone()).await {
+            Ok(summary) => summary,
+            Err(error) => {
+                eprintln!("library: skipping {:?}: {}", path, error);
+                scan.failed.push((path, stamp));
+                continue;
+            }
+        };
+
+        let (size, modified) = stamp;
+
+        scan.updated.push(LibraryEntry {
+            title: summary.title,
+            series: summary.series,
+            page_count: summary.page_count,
+            size,
+            modified,
+            path,
+        });
+    }
+
+    // Whatever wasn't found again is gone, unless its folder couldn't be read.
+    scan.removed = stamps
+        .into_iter()
+        .map(|(path, _)| path)
+        .filter(|path| {
+            let root = roots.iter().find(|root| path.starts_with(root));
+
+            root.map_or(true, |root| readable_roots.contains(root))
+        })
+        .collect();
+
+    scan
+}
+
+/// The cover of a comic, from the thumbnail cache when it is there.
+pub async fn load_cover(path: PathBuf) -> Result<iced::image::Handle, ComicError> {
+    let cover = DynamicImage::ImageRgba8(thumbnail::load(path).await?).into_bgra8();
+    let (width, height) = cover.dimensions();
+
+    Ok(iced::image::Handle::from_pixels(
+        width,
+        height,
+        cover.into_raw(),
+    ))
+}
+
+/// Finds the comic files under `directory` with their size and modification
+/// time. Hidden entries and symlinked folders are skipped, like when a
+/// folder is opened as a comic.
+fn collect_comic_files(
+    directory: &Path,
+    files: &mut Vec<(PathBuf, (u64, u64))>,
+) -> std::io::Result<()> {
+    for dir_entry in fs::read_dir(directory)? {
+        let dir_entry = dir_entry?;
+
+        if dir_entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = dir_entry.path();
+
+        if dir_entry.file_type()?.is_dir() {
+            // One unreadable subfolder shouldn't hide the rest of the library.
+            if let Err(error) = collect_comic_files(&path, files) {
+                eprintln!("library: could not read {:?}: {}", path, error);
+            }
+
+            continue;
+        }
+
+        let is_comic = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map_or(false, |extension| {
+                ARCHIVE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+            });
+
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) if is_comic && metadata.is_file() => metadata,
+            _ => continue,
+        };
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |age| age.as_secs());
+
+        files.push((path, (metadata.len(), modified)));
+    }
+
+    Ok(())
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use iced::{
+    button, scrollable, Button, Column, Container, Element, Image, Length, Row, Scrollable, Text,
+};
+
+use crate::library::{Library, LibraryEntry};
+
+/// Covers are shown scaled to fit this box, in pixels.
+const COVER_WIDTH: u32 = 160;
+const COVER_HEIGHT: u32 = 240;
+
+/// Room each comic takes up in the grid, in pixels.
+const CARD_WIDTH: u32 = COVER_WIDTH + 20;
+
+#[derive(Debug, Clone)]
+pub enum LibraryMessage {
+    Open(PathBuf),
+    Rescan,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+enum Cover {
+    Loading,
+    Loaded(iced::image::Handle),
+    /// The comic couldn't be opened to find its cover.
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+struct Card {
+    entry: LibraryEntry,
+    button: button::State,
+}
+
+/// The comics in the library as a grid of covers, to pick one to read.
+///
+/// Covers are decoded one at a time as the grid asks for them, and are kept
+/// for as long as the grid is open.
+#[derive(Debug, Clone)]
+pub struct LibraryView {
+    cards: Vec<Card>,
+    covers: HashMap<PathBuf, Cover>,
+    is_scanning: bool,
+    /// Why the index couldn't be read or updated, if it couldn't.
+    error: Option<String>,
+    scroll: scrollable::State,
+    rescan_button: button::State,
+    close_button: button::State,
+}
+
+impl LibraryView {
+    pub fn new(entries: Vec<LibraryEntry>) -> Self {
+        let mut library_view = Self {
+            cards: Vec::new(),
+            covers: HashMap::new(),
+            is_scanning: false,
+            error: None,
+            scroll: scrollable::State::new(),
+            rescan_button: button::State::new(),
+            close_button: button::State::new(),
+        };
+
+        library_view.set_entries(entries);
+
+        library_view
+    }
+
+    pub fn set_entries(&mut self, entries: Vec<LibraryEntry>) {
+        // A comic that changed on disk may have a different cover now.
+        let previous: HashMap<PathBuf, LibraryEntry> = self
+            .cards
+            .drain(..)
+            .map(|card| (card.entry.path.clone(), card.entry))
+            .collect();
+
+        for entry in &entries {
+            if previous
+                .get(&entry.path)
+                .map_or(false, |previous| previous != entry)
+            {
+                self.covers.remove(&entry.path);
+            }
+        }
+
+        self.cards = entries
+            .into_iter()
+            .map(|entry| Card {
+                entry,
+                button: button::State::new(),
+            })
+            .collect();
+    }
+
+    pub fn set_scanning(&mut self, is_scanning: bool) {
+        self.is_scanning = is_scanning;
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    /// Picks the next comic whose cover should be decoded and marks it as
+    /// being loaded.
+    pub fn next_cover_to_load(&mut self) -> Option<PathBuf> {
+        let path = self
+            .cards
+            .iter()
+            .map(|card| &card.entry.path)
+            .find(|path| !self.covers.contains_key(*path))?
+            .clone();
+
+        self.covers.insert(path.clone(), Cover::Loading);
+
+        Some(path)
+    }
+
+    /// Stores a decoded cover. Returns whether this grid was waiting for it,
+    /// a cover asked for by a grid that has since been closed is dropped.
+    pub fn set_cover(&mut self, path: PathBuf, cover: Option<iced::image::Handle>) -> bool {
+        if !matches!(self.covers.get(&path), Some(Cover::Loading)) {
+            return false;
+        }
+
+        let cover = match cover {
+            Some(handle) => Cover::Loaded(handle),
+            None => Cover::Failed,
+        };
+
+        self.covers.insert(path, cover);
+
+        true
+    }
+
+    pub fn view(&mut self, width: u32) -> Element<'_, LibraryMessage> {
+        let header = Row::new()
+            .spacing(10)
+            .push(
+                Button::new(&mut self.close_button, Text::new("Close"))
+                    .on_press(LibraryMessage::Close),
+            )
+            .push({
+                let rescan_button = Button::new(&mut self.rescan_button, Text::new("Rescan"));
+
+                // Only one scan runs at a time.
+                if self.is_scanning {
+                    rescan_button
+                } else {
+                    rescan_button.on_press(LibraryMessage::Rescan)
+                }
+            })
+            .push(
+                Text::new(match (self.is_scanning, self.cards.len()) {
+                    (true, _) => "Looking for comics".to_string(),
+                    (false, 1) => "1 comic".to_string(),
+                    (false, count) => format!("{} comics", count),
+                })
+                .size(20),
+            );
+
+        let mut grid = Scrollable::new(&mut self.scroll)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .spacing(20);
+
+        if let Some(error) = &self.error {
+            grid = grid.push(Text::new(format!("Could not read the library: {}", error)).size(16));
+        } else if self.cards.is_empty() && !self.is_scanning {
+            let config_path = Library::config_path().map_or_else(
+                || "library.toml".to_string(),
+                |path| path.to_string_lossy().into_owned(),
+            );
+
+            grid = grid
+                .push(Text::new(format!("Add folders with comics to {}", config_path)).size(16));
+        }
+
+        let columns = (width / CARD_WIDTH).max(1) as usize;
+        let covers = &self.covers;
+
+        for cards in self.cards.chunks_mut(columns) {
+            let row = cards.iter_mut().fold(Row::new().spacing(10), |row, card| {
+                let cover: Element<'_, LibraryMessage> = match covers.get(&card.entry.path) {
+                    Some(Cover::Loaded(handle)) => Image::new(handle.clone())
+                        .width(Length::Units(COVER_WIDTH as u16))
+                        .height(Length::Units(COVER_HEIGHT as u16))
+                        .into(),
+                    cover => Container::new(Text::new(match cover {
+                        Some(Cover::Failed) => "No Cover",
+                        _ => "",
+                    }))
+                    .width(Length::Units(COVER_WIDTH as u16))
+                    .height(Length::Units(COVER_HEIGHT as u16))
+                    .center_x()
+                    .center_y()
+                    .into(),
+                };
+
+                let details = match &card.entry.series {
+                    Some(series) if *series != card.entry.title => {
+                        format!("{} - {} pages", series, card.entry.page_count)
+                    }
+                    _ => format!("{} pages", card.entry.page_count),
+                };
+
+                row.push(
+                    Button::new(
+                        &mut card.button,
+                        Column::new()
+                            .spacing(4)
+                            .push(cover)
+                            .push(Text::new(card.entry.title.clone()).size(16))
+                            .push(Text::new(details).size(12)),
+                    )
+                    .width(Length::Units(CARD_WIDTH as u16))
+                    .on_press(LibraryMessage::Open(card.entry.path.clone())),
+                )
+            });
+
+            grid = grid.push(row);
+        }
+
+        Column::new()
+            .padding(20)
+            .spacing(10)
+            .push(header)
+            .push(grid)
+            .into()
+    }
+}
@@ -1,11 +1,100 @@
 #![windows_subsystem = "windows"]
 
-mod comic;
 mod app;
+mod archive_format;
+mod bookmarks;
+mod bookmarks_panel;
+mod comic;
+mod comic_info;
+mod comic_settings;
+mod epub;
+mod file_browser;
+mod hud;
 mod image_viewer;
+mod keymap;
+mod library;
+mod library_view;
+mod natural_sort;
+mod page_cache;
+mod page_navigator;
+mod page_strip;
+mod pdf;
+mod spread;
+mod stack;
+mod thumbnail;
+
+use std::{env, path::PathBuf, process};
 
 use iced::{Application, Settings};
 
+const USAGE: &str = "usage: comik [OPTIONS] [FILE]
+
+Options:
+  -p, --page <N>       open the comic at page N, counting from 1
+  -m, --mode <MODE>    reading mode: single, spread or continuous
+      --rtl            read right to left
+  -f, --fullscreen     start in fullscreen
+  -h, --help           show this message";
+
 fn main() -> iced::Result {
-    app::App::run(Settings::default())
+    let flags = match parse_args(env::args().skip(1)) {
+        Ok(Some(flags)) => flags,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(message) => {
+            eprintln!("comik: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    comic::remove_stale_temp_directories();
+
+    app::App::run(Settings::with_flags(flags))
+}
+
+/// Turns the command line into start up flags, or `None` when help was asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<app::Flags>, String> {
+    let mut flags = app::Flags::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-p" | "--page" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a page number", arg))?;
+
+                let page = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|page| *page > 0)
+                    .ok_or_else(|| format!("`{}` is not a page number", value))?;
+
+                flags.page = Some(page - 1);
+            }
+            "-m" | "--mode" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a reading mode", arg))?;
+
+                match value.as_str() {
+                    "single" => {}
+                    "spread" => flags.spread_mode = true,
+                    "continuous" => flags.continuous_mode = true,
+                    _ => return Err(format!("unknown reading mode `{}`", value)),
+                }
+            }
+            "--rtl" => flags.right_to_left = true,
+            "-f" | "--fullscreen" => flags.fullscreen = true,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}`", arg))
+            }
+            _ if flags.path.is_some() => return Err("only one file can be opened".to_string()),
+            _ => flags.path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    Ok(Some(flags))
 }
@@ -0,0 +1,148 @@
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+/// Compares two strings the way a person would, case-insensitively and with
+/// runs of digits compared by value, so `page2` sorts before `page10`.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+                let ordering =
+                    compare_numbers(&take_digits(&mut a_chars), &take_digits(&mut b_chars));
+
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(a_char), Some(b_char)) => {
+                let ordering = a_char.to_lowercase().cmp(b_char.to_lowercase());
+
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Compares two archive paths component by component, so that pages in a
+/// folder come before the folder's subfolders and chapter folders are
+/// ordered naturally, e.g. `cover.jpg` < `Chapter 2/01.jpg` < `Chapter 10/01.jpg`.
+pub fn compare_paths(a: &str, b: &str) -> Ordering {
+    let a_components: Vec<&str> = split_path(a);
+    let b_components: Vec<&str> = split_path(b);
+
+    for (index, (a_component, b_component)) in a_components.iter().zip(&b_components).enumerate() {
+        let a_is_file = index + 1 == a_components.len();
+        let b_is_file = index + 1 == b_components.len();
+
+        if a_is_file != b_is_file {
+            return if a_is_file {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+
+        let ordering = compare(a_component, b_component);
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    // Names that only differ in case or zero padding still need a stable order.
+    a_components
+        .len()
+        .cmp(&b_components.len())
+        .then_with(|| a.cmp(b))
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split(|c| c == '/' || c == '\\')
+        .filter(|component| !component.is_empty())
+        .collect()
+}
+
+fn take_digits(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut digits = String::new();
+
+    while let Some(digit) = chars.peek().copied().filter(char::is_ascii_digit) {
+        digits.push(digit);
+        chars.next();
+    }
+
+    digits
+}
+
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let a_trimmed = a.trim_start_matches('0');
+    let b_trimmed = b.trim_start_matches('0');
+
+    // Without leading zeros the longer number is the larger one, and equal
+    // lengths compare correctly digit by digit without any risk of overflow.
+    a_trimmed
+        .len()
+        .cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed))
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digit_runs_compare_by_value() {
+        assert_eq!(compare("page2", "page10"), Ordering::Less);
+        assert_eq!(compare("page10", "page9"), Ordering::Greater);
+        assert_eq!(compare("1-2", "1-10"), Ordering::Less);
+    }
+
+    #[test]
+    fn leading_zeros_do_not_change_the_value() {
+        assert_eq!(compare("page002", "page10"), Ordering::Less);
+        assert_eq!(compare("page010", "page9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn padded_numbers_sort_after_unpadded_ones_of_the_same_value() {
+        assert_eq!(compare("page01", "page1"), Ordering::Greater);
+        assert_eq!(compare("page1", "page01"), Ordering::Less);
+        assert_eq!(compare("page01", "page01"), Ordering::Equal);
+    }
+
+    #[test]
+    fn long_digit_runs_do_not_overflow() {
+        assert_eq!(
+            compare("99999999999999999999999", "100000000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn letters_compare_without_case() {
+        assert_eq!(compare("Page", "page"), Ordering::Equal);
+        assert_eq!(compare("apple", "Banana"), Ordering::Less);
+    }
+
+    #[test]
+    fn pages_come_before_subfolders() {
+        assert_eq!(
+            compare_paths("cover.jpg", "Chapter 2/01.jpg"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_paths("Chapter 2/01.jpg", "Chapter 10/01.jpg"),
+            Ordering::Less
+        );
+    }
+}
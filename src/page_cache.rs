@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::comic::{ComicError, Page, RenderSize};
+
+/// How many pages after the current one are decoded in the background.
+pub const PREFETCH_AHEAD: usize = 3;
+/// How many pages before the current one are decoded in the background.
+pub const PREFETCH_BEHIND: usize = 1;
+
+/// Decoded pages are kept until they take up more than this many bytes.
+const DEFAULT_BUDGET_BYTES: usize = 512 * 1024 * 1024;
+
+/// A page decoded into pixels, ready for the renderer to upload.
+#[derive(Debug, Clone)]
+pub struct DecodedPage {
+    pub handle: iced::image::Handle,
+    pub width: u32,
+    pub height: u32,
+    pub size_bytes: usize,
+    /// What the page was drawn to fit, if it is scalable, so it can be drawn
+    /// again once it is shown bigger.
+    pub render_size: Option<RenderSize>,
+}
+
+/// Decodes a page off the UI thread.
+///
+/// The pixels are handed to iced directly so that the renderer doesn't have to
+/// decode the image again while drawing.
+pub async fn decode_page(page: Page, render_size: RenderSize) -> Result<DecodedPage, ComicError> {
+    let image = page.as_image(render_size)?.into_bgra8();
+
+    let (width, height) = image.dimensions();
+    let pixels = image.into_raw();
+    let size_bytes = pixels.len();
+
+    Ok(DecodedPage {
+        handle: iced::image::Handle::from_pixels(width, height, pixels),
+        width,
+        height,
+        size_bytes,
+        render_size: Some(render_size).filter(|_| page.is_scalable()),
+    })
+}
+
+/// Least recently used cache of decoded pages, keyed by page file name.
+#[derive(Debug)]
+pub struct PageCache {
+    budget_bytes: usize,
+    used_bytes: usize,
+    pages: HashMap<String, DecodedPage>,
+    /// Least recently used first.
+    recency: VecDeque<String>,
+    /// Pages that are being decoded right now.
+    pending: HashSet<String>,
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        PageCache::new(DEFAULT_BUDGET_BYTES)
+    }
+}
+
+impl PageCache {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            used_bytes: 0,
+            pages: HashMap::new(),
+            recency: VecDeque::new(),
+            pending: HashSet::new(),
+        }
+    }
+
+    pub fn get(&mut self, file_name: &str) -> Option<iced::image::Handle> {
+        let handle = self.pages.get(file_name)?.handle.clone();
+        self.touch(file_name);
+
+        Some(handle)
+    }
+
+    /// Looks a page up without counting it as used.
+    pub fn peek(&self, file_name: &str) -> Option<iced::image::Handle> {
+        self.pages.get(file_name).map(|page| page.handle.clone())
+    }
+
+    /// What a cached scalable page was drawn to fit.
+    pub fn render_size(&self, file_name: &str) -> Option<RenderSize> {
+        self.pages.get(file_name)?.render_size
+    }
+
+    pub fn is_pending(&self, file_name: &str) -> bool {
+        self.pending.contains(file_name)
+    }
+
+    /// Whether the page is either cached or on its way.
+    pub fn is_requested(&self, file_name: &str) -> bool {
+        self.pages.contains_key(file_name) || self.pending.contains(file_name)
+    }
+
+    pub fn mark_pending(&mut self, file_name: &str) {
+        self.pending.insert(file_name.to_string());
+    }
+
+    pub fn cancel_pending(&mut self, file_name: &str) {
+        self.pending.remove(file_name);
+    }
+
+    pub fn insert(&mut self, file_name: String, page: DecodedPage) {
+        self.pending.remove(&file_name);
+
+        if let Some(previous) = self.pages.remove(&file_name) {
+            self.used_bytes -= previous.size_bytes;
+        }
+
+        self.used_bytes += page.size_bytes;
+        self.pages.insert(file_name.clone(), page);
+        self.touch(&file_name);
+
+        // The newest page always stays, even if it alone is over budget.
+        while self.used_bytes > self.budget_bytes && self.recency.len() > 1 {
+            if let Some(evicted) = self.recency.pop_front() {
+                if let Some(page) = self.pages.remove(&evicted) {
+                    self.used_bytes -= page.size_bytes;
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.used_bytes = 0;
+        self.pages.clear();
+        self.recency.clear();
+        self.pending.clear();
+    }
+
+    fn touch(&mut self, file_name: &str) {
+        if let Some(position) = self.recency.iter().position(|name| name == file_name) {
+            self.recency.remove(position);
+        }
+
+        self.recency.push_back(file_name.to_string());
+    }
+}
@@ -0,0 +1,303 @@
+use std::{collections::HashMap, ops::Range};
+
+use iced::{
+    button, Align, Background, Button, Color, Column, Container, Element, Image, Length, Row, Text,
+};
+use image::{imageops::FilterType, GenericImageView};
+
+use crate::comic::{ComicError, Page, RenderSize};
+
+/// Height of the navigator along the bottom of the window, in pixels.
+pub const NAVIGATOR_HEIGHT: u32 = THUMBNAIL_HEIGHT + 50;
+
+/// Thumbnails are scaled to fit this box, in pixels.
+const THUMBNAIL_WIDTH: u32 = 80;
+const THUMBNAIL_HEIGHT: u32 = 120;
+
+/// Room each page takes up in the strip, in pixels.
+const SLOT_WIDTH: u32 = THUMBNAIL_WIDTH + 18;
+/// Room taken by the buttons at either end of the strip, in pixels.
+const ARROW_WIDTH: u32 = 40;
+
+/// How many thumbnails are decoded at the same time.
+const THUMBNAIL_LOADS_AT_ONCE: usize = 2;
+
+#[derive(Debug, Clone)]
+pub enum PageNavigatorMessage {
+    GoTo(usize),
+    ShowEarlier,
+    ShowLater,
+}
+
+#[derive(Debug, Clone)]
+enum Thumbnail {
+    Loading,
+    Loaded(iced::image::Handle),
+    Failed,
+}
+
+/// A strip of page thumbnails along the bottom of the window, to see where
+/// you are in the comic and jump elsewhere.
+///
+/// Scrollables only go up and down in this version of iced, so the strip
+/// shows as many pages as fit across and is moved along a window at a time
+/// with the buttons at its ends. It follows the page being read.
+///
+/// Thumbnails are only decoded for the pages in the strip, a couple at a time.
+#[derive(Debug, Clone, Default)]
+pub struct PageNavigator {
+    /// The first page in the strip.
+    first_shown: usize,
+    /// The page the strip was last moved to show, so the strip only follows
+    /// the reader when they turn the page and not when they look around.
+    followed_page: Option<usize>,
+    /// Thumbnails by page file name.
+    thumbnails: HashMap<String, Thumbnail>,
+    loading_count: usize,
+    page_buttons: Vec<button::State>,
+    left_button: button::State,
+    right_button: button::State,
+}
+
+impl PageNavigator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many pages fit in a strip this wide.
+    fn capacity(width: u32) -> usize {
+        (width.saturating_sub(2 * ARROW_WIDTH) / SLOT_WIDTH).max(1) as usize
+    }
+
+    /// The pages in the strip, out of `page_count`.
+    fn shown(&self, page_count: usize, width: u32) -> Range<usize> {
+        let first = self.first_shown.min(page_count.saturating_sub(1));
+
+        first..(first + PageNavigator::capacity(width)).min(page_count)
+    }
+
+    /// Moves the strip to have the page being read in the middle, unless it
+    /// is already there or the strip was moved for it before.
+    pub fn follow(&mut self, current_page: usize, page_count: usize, width: u32) {
+        if self.followed_page == Some(current_page) {
+            return;
+        }
+
+        self.followed_page = Some(current_page);
+
+        if !self.shown(page_count, width).contains(&current_page) {
+            self.first_shown = current_page.saturating_sub(PageNavigator::capacity(width) / 2);
+        }
+    }
+
+    /// Moves the strip a whole window of pages earlier or later.
+    pub fn show_more(&mut self, message: &PageNavigatorMessage, page_count: usize, width: u32) {
+        let capacity = PageNavigator::capacity(width);
+        let last_first = page_count.saturating_sub(capacity);
+
+        self.first_shown = match message {
+            PageNavigatorMessage::ShowEarlier => self.first_shown.saturating_sub(capacity),
+            PageNavigatorMessage::ShowLater => (self.first_shown + capacity).min(last_first),
+            PageNavigatorMessage::GoTo(_) => self.first_shown,
+        };
+    }
+
+    /// Picks the next page in the strip whose thumbnail should be decoded
+    /// and marks it as being loaded, as long as not too many already are.
+    pub fn next_thumbnail_to_load<'a>(
+        &mut self,
+        pages: &'a [Page],
+        width: u32,
+    ) -> Option<&'a Page> {
+        if self.loading_count >= THUMBNAIL_LOADS_AT_ONCE {
+            return None;
+        }
+
+        let page = pages[self.shown(pages.len(), width)]
+            .iter()
+            .find(|page| !self.thumbnails.contains_key(page.file_name()))?;
+
+        self.thumbnails
+            .insert(page.file_name().to_string(), Thumbnail::Loading);
+        self.loading_count += 1;
+
+        Some(page)
+    }
+
+    /// Stores a decoded thumbnail, `None` meaning the page couldn't be read.
+    pub fn set_thumbnail(&mut self, file_name: String, thumbnail: Option<iced::image::Handle>) {
+        if !matches!(self.thumbnails.get(&file_name), Some(Thumbnail::Loading)) {
+            return;
+        }
+
+        self.loading_count -= 1;
+
+        let thumbnail = match thumbnail {
+            Some(handle) => Thumbnail::Loaded(handle),
+            None => Thumbnail::Failed,
+        };
+
+        self.thumbnails.insert(file_name, thumbnail);
+    }
+
+    /// The strip for `pages`, with the pages in `current` highlighted. Right
+    /// to left, the strip runs the same way as the pages are turned.
+    pub fn view(
+        &mut self,
+        pages: &[Page],
+        current: Range<usize>,
+        width: u32,
+        right_to_left: bool,
+    ) -> Element<'_, PageNavigatorMessage> {
+        let shown = self.shown(pages.len(), width);
+
+        let has_earlier = shown.start > 0;
+        let has_later = shown.end < pages.len();
+
+        self.page_buttons
+            .resize_with(shown.len(), button::State::new);
+
+        let thumbnails = &self.thumbnails;
+
+        let mut slots: Vec<Element<'_, PageNavigatorMessage>> = self
+            .page_buttons
+            .iter_mut()
+            .zip(shown)
+            .map(|(state, index)| {
+                let thumbnail: Element<'_, PageNavigatorMessage> =
+                    match thumbnails.get(pages[index].file_name()) {
+                        Some(Thumbnail::Loaded(handle)) => Image::new(handle.clone())
+                            .width(Length::Units(THUMBNAIL_WIDTH as u16))
+                            .height(Length::Units(THUMBNAIL_HEIGHT as u16))
+                            .into(),
+                        thumbnail => Container::new(Text::new(match thumbnail {
+                            Some(Thumbnail::Failed) => "?",
+                            _ => "",
+                        }))
+                        .width(Length::Units(THUMBNAIL_WIDTH as u16))
+                        .height(Length::Units(THUMBNAIL_HEIGHT as u16))
+                        .center_x()
+                        .center_y()
+                        .into(),
+                    };
+
+                Button::new(
+                    state,
+                    Column::new()
+                        .spacing(4)
+                        .align_items(Align::Center)
+                        .push(thumbnail)
+                        .push(Text::new((index + 1).to_string()).size(14)),
+                )
+                .padding(4)
+                .style(if current.contains(&index) {
+                    SlotStyle::Current
+                } else {
+                    SlotStyle::Other
+                })
+                .on_press(PageNavigatorMessage::GoTo(index))
+                .into()
+            })
+            .collect();
+
+        let (left_message, right_message, has_left, has_right) = if right_to_left {
+            slots.reverse();
+
+            (
+                PageNavigatorMessage::ShowLater,
+                PageNavigatorMessage::ShowEarlier,
+                has_later,
+                has_earlier,
+            )
+        } else {
+            (
+                PageNavigatorMessage::ShowEarlier,
+                PageNavigatorMessage::ShowLater,
+                has_earlier,
+                has_later,
+            )
+        };
+
+        let arrow = |state, label, message, is_enabled| {
+            let button =
+                Button::new(state, Text::new(label)).width(Length::Units(ARROW_WIDTH as u16));
+
+            if is_enabled {
+                button.on_press(message)
+            } else {
+                button
+            }
+        };
+
+        let strip = slots
+            .into_iter()
+            .fold(Row::new().spacing(8).align_items(Align::Center), Row::push);
+
+        Row::new()
+            .width(Length::Fill)
+            .height(Length::Units(NAVIGATOR_HEIGHT as u16))
+            .align_items(Align::Center)
+            .push(arrow(&mut self.left_button, "<", left_message, has_left))
+            .push(Container::new(strip).width(Length::Fill).center_x())
+            .push(arrow(&mut self.right_button, ">", right_message, has_right))
+            .into()
+    }
+}
+
+/// Decodes a page off the UI thread and scales it down to a thumbnail.
+pub async fn make_thumbnail(page: Page) -> Result<iced::image::Handle, ComicError> {
+    // Scalable pages are drawn at about the right size to begin with.
+    let image = page.as_image(RenderSize {
+        width: THUMBNAIL_WIDTH * 2,
+        height: THUMBNAIL_HEIGHT * 2,
+    })?;
+
+    let image = if image.width() > THUMBNAIL_WIDTH || image.height() > THUMBNAIL_HEIGHT {
+        image.resize(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Triangle)
+    } else {
+        image
+    }
+    .into_bgra8();
+
+    let (width, height) = image.dimensions();
+
+    Ok(iced::image::Handle::from_pixels(
+        width,
+        height,
+        image.into_raw(),
+    ))
+}
+
+/// Thumbnails sit on the window background, the page being read on a
+/// highlight.
+enum SlotStyle {
+    Current,
+    Other,
+}
+
+impl button::StyleSheet for SlotStyle {
+    fn active(&self) -> button::Style {
+        match self {
+            SlotStyle::Current => button::Style {
+                background: Some(Background::Color(Color::from_rgb(0.35, 0.55, 0.9))),
+                border_radius: 4.0,
+                text_color: Color::WHITE,
+                ..button::Style::default()
+            },
+            SlotStyle::Other => button::Style {
+                border_radius: 4.0,
+                ..button::Style::default()
+            },
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        match self {
+            SlotStyle::Current => self.active(),
+            SlotStyle::Other => button::Style {
+                background: Some(Background::Color(Color::from_rgb(0.85, 0.85, 0.85))),
+                ..self.active()
+            },
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use iced_native::{
+    clipboard::Clipboard, column, event, layout, mouse, Element, Event, Hasher, Layout, Length,
+    Point, Rectangle, Size, Widget,
+};
+use std::hash::Hash;
+
+/// How many pixels one line of a mouse wheel scrolls the strip.
+const PIXELS_PER_LINE: f32 = 60.0;
+
+/// Height of a page relative to its width until the page has been decoded
+/// and its real size is known.
+const PLACEHOLDER_ASPECT_RATIO: f32 = 1.5;
+
+/// Where every page of a comic sits when they are all stacked on top of each
+/// other, in pixels from the top of the strip.
+#[derive(Debug, Clone, Default)]
+pub struct StripLayout {
+    pages: Vec<Rectangle>,
+}
+
+impl StripLayout {
+    /// Stacks the pages in a strip `width` pixels wide. Pages are shrunk to
+    /// fit the width but never enlarged, and centered when narrower.
+    pub fn new(
+        width: f32,
+        page_count: usize,
+        page_size: impl Fn(usize) -> Option<(u32, u32)>,
+    ) -> Self {
+        let mut top = 0.0;
+
+        let pages = (0..page_count)
+            .map(|index| {
+                let (page_width, page_height) = match page_size(index) {
+                    Some((page_width, page_height)) if page_width > 0 => {
+                        let scaled_width = (page_width as f32).min(width);
+                        (
+                            scaled_width,
+                            scaled_width * page_height as f32 / page_width as f32,
+                        )
+                    }
+                    _ => (width, width * PLACEHOLDER_ASPECT_RATIO),
+                };
+
+                let bounds = Rectangle {
+                    x: ((width - page_width) / 2.0).round(),
+                    y: top,
+                    width: page_width,
+                    height: page_height.round(),
+                };
+
+                top += bounds.height;
+
+                bounds
+            })
+            .collect();
+
+        Self { pages }
+    }
+
+    pub fn page(&self, index: usize) -> Option<Rectangle> {
+        self.pages.get(index).copied()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.pages.last().map_or(0.0, |page| page.y + page.height)
+    }
+
+    /// The page covering the point `y` pixels down the strip, or the closest one.
+    pub fn page_at(&self, y: f32) -> usize {
+        self.pages
+            .iter()
+            .position(|page| y < page.y + page.height)
+            .unwrap_or_else(|| self.pages.len().saturating_sub(1))
+    }
+
+    /// Indices of the pages that overlap the band from `top` to `bottom`.
+    pub fn pages_between(&self, top: f32, bottom: f32) -> std::ops::Range<usize> {
+        let first = self.page_at(top);
+        let last = self.page_at(bottom);
+
+        first..(last + 1).min(self.pages.len())
+    }
+}
+
+/// Pages stacked vertically and scrolled as one long strip.
+///
+/// Only the pages near the viewport are passed in as children, placed at
+/// their position in the strip, so a long comic costs no more to lay out and
+/// draw than a short one. The scroll position is owned by the application,
+/// the strip just reports how far the reader wants to scroll.
+pub struct PageStrip<'a, Message, Renderer> {
+    offset: f32,
+    bounds: Vec<Rectangle>,
+    children: Vec<Element<'a, Message, Renderer>>,
+    on_scroll: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+}
+
+impl<'a, Message, Renderer> PageStrip<'a, Message, Renderer>
+where
+    Renderer: column::Renderer,
+{
+    /// Creates a strip scrolled `offset` pixels down from its top.
+    pub fn new(offset: f32) -> Self {
+        PageStrip {
+            offset,
+            bounds: Vec::new(),
+            children: Vec::new(),
+            on_scroll: None,
+        }
+    }
+
+    /// Adds a page to the strip, drawn in `bounds` given in strip coordinates.
+    pub fn push(
+        mut self,
+        bounds: Rectangle,
+        child: impl Into<Element<'a, Message, Renderer>>,
+    ) -> Self {
+        self.bounds.push(bounds);
+        self.children.push(child.into());
+        self
+    }
+
+    /// The message to send when the wheel scrolls the strip, with the number
+    /// of pixels to scroll down by.
+    pub fn on_scroll(mut self, f: impl Fn(f32) -> Message + 'a) -> Self {
+        self.on_scroll = Some(Box::new(f));
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for PageStrip<'a, Message, Renderer>
+where
+    Renderer: column::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let size = limits.width(Length::Fill).height(Length::Fill).max();
+
+        let children = self
+            .bounds
+            .iter()
+            .zip(&self.children)
+            .map(|(bounds, child)| {
+                let limits = layout::Limits::new(Size::ZERO, bounds.size());
+                let mut node = child.layout(renderer, &limits);
+
+                node.move_to(Point::new(bounds.x, bounds.y - self.offset));
+
+                node
+            })
+            .collect();
+
+        layout::Node::with_children(size, children)
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Message>,
+    ) -> event::Status {
+        let is_mouse_over = layout.bounds().contains(cursor_position);
+
+        match (event, &self.on_scroll) {
+            (Event::Mouse(mouse::Event::WheelScrolled { delta }), Some(on_scroll))
+                if is_mouse_over =>
+            {
+                let pixels = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y * PIXELS_PER_LINE,
+                    mouse::ScrollDelta::Pixels { y, .. } => y,
+                };
+
+                // Wheel deltas are positive when scrolling up.
+                messages.push(on_scroll(-pixels));
+
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        defaults: &Renderer::Defaults,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) -> Renderer::Output {
+        column::Renderer::draw(
+            renderer,
+            defaults,
+            &self.children,
+            layout,
+            cursor_position,
+            viewport,
+        )
+    }
+
+    fn hash_layout(&self, state: &mut Hasher) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+
+        self.offset.to_bits().hash(state);
+
+        for (bounds, child) in self.bounds.iter().zip(&self.children) {
+            bounds.x.to_bits().hash(state);
+            bounds.y.to_bits().hash(state);
+            bounds.width.to_bits().hash(state);
+            bounds.height.to_bits().hash(state);
+
+            child.hash_layout(state);
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<PageStrip<'a, Message, Renderer>>
+    for Element<'a, Message, Renderer>
+where
+    Renderer: 'a + column::Renderer,
+    Message: 'a,
+{
+    fn from(strip: PageStrip<'a, Message, Renderer>) -> Element<'a, Message, Renderer> {
+        Element::new(strip)
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use image::{DynamicImage, RgbImage};
+use mupdf::{Colorspace, Document, Matrix};
+
+use crate::comic::{ComicError, RenderSize};
+
+/// Pages are never drawn bigger than this on either side, however far in
+/// the reader zooms, to keep a single page from eating all the memory.
+const MAX_RENDER_DIMENSION: f32 = 8192.0;
+
+fn open(path: &Path) -> Result<Document, ComicError> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| ComicError::Io(format!("{:?} is not a valid UTF-8 path", path)))?;
+
+    let document =
+        Document::open(path).map_err(|error| ComicError::CorruptArchive(error.to_string()))?;
+
+    if document
+        .needs_password()
+        .map_err(|error| ComicError::CorruptArchive(error.to_string()))?
+    {
+        return Err(ComicError::EncryptedArchive);
+    }
+
+    Ok(document)
+}
+
+pub fn page_count(path: &Path) -> Result<usize, ComicError> {
+    let page_count = open(path)?
+        .page_count()
+        .map_err(|error| ComicError::CorruptArchive(error.to_string()))?;
+
+    Ok(page_count.max(0) as usize)
+}
+
+/// Rasterizes a page on the CPU so that it fits inside `render_size`.
+///
+/// The document is opened again for every page, MuPDF documents can't be
+/// shared between the threads pages are decoded on.
+pub fn render_page(
+    path: &Path,
+    index: usize,
+    render_size: RenderSize,
+) -> Result<DynamicImage, String> {
+    let document = open(path).map_err(|error| error.to_string())?;
+    let page = document
+        .load_page(index as i32)
+        .map_err(|error| error.to_string())?;
+    let bounds = page.bounds().map_err(|error| error.to_string())?;
+
+    let (page_width, page_height) = (bounds.width().max(1.0), bounds.height().max(1.0));
+
+    let scale = (render_size.width as f32 / page_width)
+        .min(render_size.height as f32 / page_height)
+        .min(MAX_RENDER_DIMENSION / page_width)
+        .min(MAX_RENDER_DIMENSION / page_height);
+
+    let pixmap = page
+        .to_pixmap(
+            &Matrix::new_scale(scale, scale),
+            &Colorspace::device_rgb(),
+            0.0,
+            false,
+        )
+        .map_err(|error| error.to_string())?;
+
+    let (width, height) = (pixmap.width(), pixmap.height());
+    let components = pixmap.n() as usize;
+    let stride = pixmap.stride() as usize;
+    let samples = pixmap.samples();
+
+    // Rows may be padded, so the pixels are copied out one row at a time.
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+
+    for row in samples.chunks(stride).take(height as usize) {
+        for pixel in row.chunks(components).take(width as usize) {
+            pixels.extend_from_slice(&pixel[..3]);
+        }
+    }
+
+    RgbImage::from_raw(width, height, pixels)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "rendered page has the wrong size".to_string())
+}
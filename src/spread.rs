@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+/// Groups the pages of a comic into what is shown at once in spread mode.
+///
+/// The cover and any page that is already a two-page spread on its own are
+/// shown alone, everything else is paired up in reading order.
+pub fn layout(page_count: usize, is_wide: impl Fn(usize) -> bool) -> Vec<Range<usize>> {
+    let mut spreads = Vec::new();
+    let mut index = 0;
+
+    while index < page_count {
+        let pairs_with_next =
+            index != 0 && index + 1 < page_count && !is_wide(index) && !is_wide(index + 1);
+
+        let end = if pairs_with_next {
+            index + 2
+        } else {
+            index + 1
+        };
+
+        spreads.push(index..end);
+        index = end;
+    }
+
+    spreads
+}
+
+/// Whether an image is wide enough to be a spread by itself.
+pub fn is_wide(width: u32, height: u32) -> bool {
+    width > height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_cover_is_shown_alone() {
+        assert_eq!(layout(5, |_| false), vec![0..1, 1..3, 3..5]);
+    }
+
+    #[test]
+    fn a_last_page_without_a_partner_is_shown_alone() {
+        assert_eq!(layout(4, |_| false), vec![0..1, 1..3, 3..4]);
+    }
+
+    #[test]
+    fn wide_pages_are_shown_alone() {
+        assert_eq!(
+            layout(6, |index| index == 2),
+            vec![0..1, 1..2, 2..3, 3..5, 5..6]
+        );
+        assert_eq!(layout(4, |index| index == 1), vec![0..1, 1..2, 2..4]);
+    }
+
+    #[test]
+    fn a_comic_without_pages_has_no_spreads() {
+        assert!(layout(0, |_| false).is_empty());
+        assert_eq!(layout(1, |_| false), vec![0..1]);
+    }
+
+    #[test]
+    fn pages_wider_than_tall_are_wide() {
+        assert!(is_wide(2000, 1500));
+        assert!(!is_wide(1000, 1500));
+        assert!(!is_wide(1500, 1500));
+    }
+}
@@ -0,0 +1,125 @@
+use std::hash::Hash;
+
+use iced_native::{
+    clipboard::Clipboard, column, event, layout, Element, Event, Hasher, Layout, Length, Point,
+    Rectangle, Widget,
+};
+
+/// Widgets drawn on top of each other, each given the whole area, the later
+/// ones over the earlier ones.
+///
+/// Events go to the topmost widget first and only reach the ones below if
+/// it doesn't capture them, so something shown over the pages doesn't stop
+/// them from being dragged and zoomed.
+pub struct Stack<'a, Message, Renderer> {
+    children: Vec<Element<'a, Message, Renderer>>,
+}
+
+impl<'a, Message, Renderer> Stack<'a, Message, Renderer>
+where
+    Renderer: column::Renderer,
+{
+    pub fn new(base: impl Into<Element<'a, Message, Renderer>>) -> Self {
+        Stack {
+            children: vec![base.into()],
+        }
+    }
+
+    /// Adds a widget over the ones already in the stack.
+    pub fn push(mut self, child: impl Into<Element<'a, Message, Renderer>>) -> Self {
+        self.children.push(child.into());
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Stack<'a, Message, Renderer>
+where
+    Renderer: column::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(Length::Fill).height(Length::Fill);
+        let size = limits.max();
+
+        let children = self
+            .children
+            .iter()
+            .map(|child| child.layout(renderer, &limits))
+            .collect();
+
+        layout::Node::with_children(size, children)
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Message>,
+    ) -> event::Status {
+        let layouts: Vec<Layout<'_>> = layout.children().collect();
+
+        for (child, layout) in self.children.iter_mut().zip(layouts).rev() {
+            let status = child.on_event(
+                event.clone(),
+                layout,
+                cursor_position,
+                renderer,
+                clipboard,
+                messages,
+            );
+
+            if status == event::Status::Captured {
+                return status;
+            }
+        }
+
+        event::Status::Ignored
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        defaults: &Renderer::Defaults,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) -> Renderer::Output {
+        column::Renderer::draw(
+            renderer,
+            defaults,
+            &self.children,
+            layout,
+            cursor_position,
+            viewport,
+        )
+    }
+
+    fn hash_layout(&self, state: &mut Hasher) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+
+        for child in &self.children {
+            child.hash_layout(state);
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Stack<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Renderer: 'a + column::Renderer,
+    Message: 'a,
+{
+    fn from(stack: Stack<'a, Message, Renderer>) -> Element<'a, Message, Renderer> {
+        Element::new(stack)
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use image::{imageops::FilterType, DynamicImage, GenericImageView, RgbaImage};
+
+use crate::comic::{Comic, ComicError, RenderSize};
+
+/// Thumbnails are made in the freedesktop "large" size, which fits a
+/// 256 pixel square, and kept in the cache folder for that size.
+const THUMBNAIL_SIZE: u32 = 256;
+const THUMBNAIL_DIRECTORY: &str = "large";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Characters left as they are in a `file://` URI, the same set GLib keeps,
+/// so the cache is shared with file managers rather than duplicating it.
+const URI_SAFE_CHARACTERS: &[u8] = b"-._~!$&'()*+,;=:@/";
+
+/// Gets the thumbnail of the comic at `path`, from the cache if it is there
+/// and the comic hasn't changed since, and made from its first page if not.
+///
+/// The cache follows the freedesktop thumbnail spec: thumbnails are PNGs
+/// named after the MD5 of the comic's URI, and carry that URI and the
+/// comic's modification time to tell whether they are still current.
+pub async fn load(path: PathBuf) -> Result<RgbaImage, ComicError> {
+    let path = fs::canonicalize(&path)?;
+    let metadata = fs::metadata(&path)?;
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |age| age.as_secs());
+
+    let uri = file_uri(&path);
+    let cache_path = cache_path(&uri);
+
+    if let Some(thumbnail) = cache_path
+        .as_deref()
+        .and_then(|cache_path| read_cached(cache_path, &uri, modified))
+    {
+        return Ok(thumbnail);
+    }
+
+    let cover = Comic::cover_image(
+        path,
+        RenderSize {
+            width: THUMBNAIL_SIZE,
+            height: THUMBNAIL_SIZE,
+        },
+    )
+    .await?;
+
+    // Never scaled up, a small cover is as good as it gets already.
+    let thumbnail = if cover.width() > THUMBNAIL_SIZE || cover.height() > THUMBNAIL_SIZE {
+        cover.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Lanczos3)
+    } else {
+        cover
+    }
+    .into_rgba8();
+
+    // The cache is only there to make things faster, failing to write it
+    // doesn't make the thumbnail any less good.
+    if let Some(cache_path) = cache_path {
+        let text = [
+            ("Thumb::URI", uri),
+            ("Thumb::MTime", modified.to_string()),
+            ("Thumb::Size", metadata.len().to_string()),
+            ("Software", "comik".to_string()),
+        ];
+
+        if let Err(error) = write_cached(&cache_path, &thumbnail, &text) {
+            eprintln!("thumbnail: could not write {:?}: {}", cache_path, error);
+        }
+    }
+
+    Ok(thumbnail)
+}
+
+fn cache_path(uri: &str) -> Option<PathBuf> {
+    let file_name = format!("{:x}.png", md5::compute(uri.as_bytes()));
+
+    dirs::cache_dir().map(|dir| {
+        dir.join("thumbnails")
+            .join(THUMBNAIL_DIRECTORY)
+            .join(file_name)
+    })
+}
+
+/// The `file://` URI of an absolute path, with everything but the safe
+/// characters percent encoded byte by byte.
+fn file_uri(path: &Path) -> String {
+    let mut uri = "file://".to_string();
+
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || URI_SAFE_CHARACTERS.contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    uri
+}
+
+/// The cached thumbnail at `cache_path`, as long as it was made for `uri`
+/// as it was at `modified`.
+fn read_cached(cache_path: &Path, uri: &str, modified: u64) -> Option<RgbaImage> {
+    let png = fs::read(cache_path).ok()?;
+    let text = text_chunks(&png);
+
+    let is_current = text.get("Thumb::URI").map(String::as_str) == Some(uri)
+        && text
+            .get("Thumb::MTime")
+            .and_then(|mtime| mtime.parse::<u64>().ok())
+            == Some(modified);
+
+    if !is_current {
+        return None;
+    }
+
+    image::load_from_memory(&png)
+        .ok()
+        .map(DynamicImage::into_rgba8)
+}
+
+/// Writes the thumbnail next to where it belongs and renames it into place,
+/// so no other program ever reads half a file.
+fn write_cached(
+    cache_path: &Path,
+    thumbnail: &RgbaImage,
+    text: &[(&str, String)],
+) -> io::Result<()> {
+    let mut png = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut png, thumbnail.width(), thumbnail.height());
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+
+        for (keyword, value) in text {
+            let chunk: Vec<u8> = keyword
+                .bytes()
+                .chain(Some(0))
+                .chain(value.bytes())
+                .collect();
+            writer.write_chunk(*b"tEXt", &chunk).map_err(to_io_error)?;
+        }
+
+        writer
+            .write_image_data(thumbnail.as_raw())
+            .map_err(to_io_error)?;
+    }
+
+    let directory = cache_path.parent().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "thumbnail path has no folder")
+    })?;
+
+    fs::create_dir_all(directory)?;
+
+    let temp_path = cache_path.with_extension(format!("png.comik-{}", std::process::id()));
+
+    create_private_file(&temp_path)?.write_all(&png)?;
+    fs::rename(&temp_path, cache_path)
+}
+
+/// Thumbnails can give away what someone has been reading, so the spec has
+/// them readable by their owner only.
+#[cfg(unix)]
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+/// The `tEXt` chunks of a PNG by keyword. Their text is Latin-1, which the
+/// keys the spec uses never go beyond.
+fn text_chunks(png: &[u8]) -> HashMap<String, String> {
+    let mut text = HashMap::new();
+
+    if !png.starts_with(PNG_SIGNATURE) {
+        return text;
+    }
+
+    let mut offset = PNG_SIGNATURE.len();
+
+    // Each chunk is its length, its type, its data and a checksum.
+    while let Some(header) = png.get(offset..offset + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+
+        let data = match png.get(offset + 8..offset + 8 + length) {
+            Some(data) => data,
+            None => break,
+        };
+
+        if &header[4..8] == b"tEXt" {
+            if let Some(separator) = data.iter().position(|byte| *byte == 0) {
+                let latin1 =
+                    |bytes: &[u8]| bytes.iter().map(|byte| *byte as char).collect::<String>();
+
+                text.insert(latin1(&data[..separator]), latin1(&data[separator + 1..]));
+            }
+        }
+
+        offset += 12 + length;
+    }
+
+    text
+}
+
+fn to_io_error(error: png::EncodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        // The checksum isn't looked at.
+        chunk.extend_from_slice(&[0; 4]);
+
+        chunk
+    }
+
+    #[test]
+    fn file_uri_percent_encodes_unsafe_bytes() {
+        assert_eq!(
+            file_uri(Path::new("/comics/Été #1 [scan].cbz")),
+            "file:///comics/%C3%89t%C3%A9%20%231%20%5Bscan%5D.cbz"
+        );
+    }
+
+    #[test]
+    fn file_uri_keeps_safe_characters() {
+        assert_eq!(
+            file_uri(Path::new("/a-b_c.d~e/f(1),g.cbz")),
+            "file:///a-b_c.d~e/f(1),g.cbz"
+        );
+    }
+
+    #[test]
+    fn text_chunks_reads_keywords_and_values() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(chunk(b"IHDR", &[0; 13]));
+        png.extend(chunk(b"tEXt", b"Thumb::URI\0file:///a.cbz"));
+        png.extend(chunk(b"tEXt", b"Thumb::MTime\x0042"));
+        png.extend(chunk(b"IEND", &[]));
+
+        let text = text_chunks(&png);
+
+        assert_eq!(
+            text.get("Thumb::URI").map(String::as_str),
+            Some("file:///a.cbz")
+        );
+        assert_eq!(text.get("Thumb::MTime").map(String::as_str), Some("42"));
+    }
+
+    #[test]
+    fn text_chunks_stops_at_a_truncated_chunk() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(chunk(b"tEXt", b"Thumb::URI\0file:///a.cbz"));
+
+        let mut truncated = chunk(b"tEXt", b"Thumb::MTime\x0042");
+        truncated.truncate(12);
+        png.extend(truncated);
+
+        let text = text_chunks(&png);
+
+        assert_eq!(
+            text.get("Thumb::URI").map(String::as_str),
+            Some("file:///a.cbz")
+        );
+        assert_eq!(text.get("Thumb::MTime"), None);
+    }
+
+    #[test]
+    fn text_chunks_ignores_files_that_are_not_png() {
+        assert!(text_chunks(b"GIF89a\0\0\0\0").is_empty());
+    }
+}